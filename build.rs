@@ -2,28 +2,55 @@ const COMMANDS: &[&str] = &[
     "stop_monitor",
     "start_monitor",
     "is_monitor_running",
+    "subscribe_format_events",
+    "unsubscribe_format_events",
     "has_text",
     "has_image",
     "has_html",
     "has_rtf",
     "has_files",
+    "has_custom",
+    "list_formats",
     "available_types",
+    "read_all",
+    "clipboard_fingerprint",
     "read_text",
     "read_files",
     "read_files_uris",
     "read_html",
+    "image_info",
     "read_image_base64",
     "read_image_binary",
+    "read_image_rgba",
+    "read_image_thumbnail",
     "read_rtf",
+    "read_custom",
+    "read_chunked",
+    "cancel_transfer",
     "write_text",
     "write_html",
     "write_html_and_text",
     "write_rtf",
+    "write",
+    "write_custom",
     "write_image_binary",
     "write_image_base64",
+    "write_image_rgba",
+    "write_chunked_begin",
+    "write_chunked_append",
+    "write_chunked_finish",
     "write_files_uris",
     "write_files",
     "clear",
+    "history_list",
+    "history_get",
+    "history_delete",
+    "history_clear",
+    "history_search",
+    "history_pin",
+    "history_unpin",
+    "history_set_tags",
+    "history_restore",
 ];
 
 fn main() {
@@ -1,9 +1,21 @@
 import * as v from "valibot"
-import { invoke } from "@tauri-apps/api/core"
+import { invoke, Channel } from "@tauri-apps/api/core"
 import { emit, listen, UnlistenFn } from "@tauri-apps/api/event"
 
 const buildCmd = (cmd: string) => `plugin:clipboard|${cmd}`
-const buildEventUrl = (event: string) => `plugin:clipboard://${event}`
+const DEFAULT_EVENT_PREFIX = "plugin:clipboard://"
+let eventPrefix = DEFAULT_EVENT_PREFIX
+const buildEventUrl = (event: string) => `${DEFAULT_EVENT_PREFIX}${event}`
+/** `event`, one of the event constants below, with the prefix set by `setEventPrefix` */
+const prefixed = (event: string) => eventPrefix + event.slice(DEFAULT_EVENT_PREFIX.length)
+
+/**
+ * Set the prefix of the events listened to, when the plugin is configured with a custom `eventPrefix`.
+ * The exported event constants keep the default `plugin:clipboard://` prefix.
+ */
+export function setEventPrefix(prefix: string) {
+  eventPrefix = prefix
+}
 
 export const START_MONITOR_COMMAND = buildCmd("start_monitor")
 export const STOP_MONITOR_COMMAND = buildCmd("stop_monitor")
@@ -15,28 +27,56 @@ export const FILES_CHANGED = buildEventUrl("files-changed")
 export const IMAGE_CHANGED = buildEventUrl("image-changed")
 export const IMAGE_BINARY_CHANGED = buildEventUrl("image-changed-binary")
 export const IS_MONITOR_RUNNING_COMMAND = buildCmd("is_monitor_running")
+export const SUBSCRIBE_FORMAT_EVENTS_COMMAND = buildCmd("subscribe_format_events")
+export const UNSUBSCRIBE_FORMAT_EVENTS_COMMAND = buildCmd("unsubscribe_format_events")
 export const HAS_TEXT_COMMAND = buildCmd("has_text")
 export const HAS_IMAGE_COMMAND = buildCmd("has_image")
 export const HAS_HTML_COMMAND = buildCmd("has_html")
 export const HAS_RTF_COMMAND = buildCmd("has_rtf")
 export const HAS_FILES_COMMAND = buildCmd("has_files")
+export const HAS_CUSTOM_COMMAND = buildCmd("has_custom")
+export const LIST_FORMATS_COMMAND = buildCmd("list_formats")
 export const AVAILABLE_TYPES_COMMAND = buildCmd("available_types")
+export const READ_ALL_COMMAND = buildCmd("read_all")
+export const CLIPBOARD_FINGERPRINT_COMMAND = buildCmd("clipboard_fingerprint")
 export const WRITE_TEXT_COMMAND = buildCmd("write_text")
 export const WRITE_HTML_COMMAND = buildCmd("write_html")
 export const WRITE_HTML_AND_TEXT_COMMAND = buildCmd("write_html_and_text")
 export const WRITE_RTF_COMMAND = buildCmd("write_rtf")
+export const WRITE_COMMAND = buildCmd("write")
+export const WRITE_CUSTOM_COMMAND = buildCmd("write_custom")
 export const WRITE_FILES_URIS_COMMAND = buildCmd("write_files_uris")
 export const WRITE_FILES_COMMAND = buildCmd("write_files")
 export const CLEAR_COMMAND = buildCmd("clear")
 export const READ_TEXT_COMMAND = buildCmd("read_text")
 export const READ_HTML_COMMAND = buildCmd("read_html")
 export const READ_RTF_COMMAND = buildCmd("read_rtf")
+export const READ_CUSTOM_COMMAND = buildCmd("read_custom")
 export const READ_FILES_COMMAND = buildCmd("read_files")
 export const READ_FILES_URIS_COMMAND = buildCmd("read_files_uris")
 export const READ_IMAGE_BINARY_COMMAND = buildCmd("read_image_binary")
 export const READ_IMAGE_BASE64_COMMAND = buildCmd("read_image_base64")
+export const READ_IMAGE_RGBA_COMMAND = buildCmd("read_image_rgba")
+export const IMAGE_INFO_COMMAND = buildCmd("image_info")
+export const READ_IMAGE_THUMBNAIL_COMMAND = buildCmd("read_image_thumbnail")
 export const WRITE_IMAGE_BINARY_COMMAND = buildCmd("write_image_binary")
 export const WRITE_IMAGE_BASE64_COMMAND = buildCmd("write_image_base64")
+export const WRITE_IMAGE_RGBA_COMMAND = buildCmd("write_image_rgba")
+export const READ_CHUNKED_COMMAND = buildCmd("read_chunked")
+export const WRITE_CHUNKED_BEGIN_COMMAND = buildCmd("write_chunked_begin")
+export const WRITE_CHUNKED_APPEND_COMMAND = buildCmd("write_chunked_append")
+export const WRITE_CHUNKED_FINISH_COMMAND = buildCmd("write_chunked_finish")
+export const CANCEL_TRANSFER_COMMAND = buildCmd("cancel_transfer")
+export const HISTORY_LIST_COMMAND = buildCmd("history_list")
+export const HISTORY_GET_COMMAND = buildCmd("history_get")
+export const HISTORY_DELETE_COMMAND = buildCmd("history_delete")
+export const HISTORY_CLEAR_COMMAND = buildCmd("history_clear")
+export const HISTORY_SEARCH_COMMAND = buildCmd("history_search")
+export const HISTORY_PIN_COMMAND = buildCmd("history_pin")
+export const HISTORY_UNPIN_COMMAND = buildCmd("history_unpin")
+export const HISTORY_SET_TAGS_COMMAND = buildCmd("history_set_tags")
+export const HISTORY_RESTORE_COMMAND = buildCmd("history_restore")
+export const HISTORY_CHANGED_EVENT = buildEventUrl("history-changed")
 export const CLIPBOARD_MONITOR_STATUS_UPDATE_EVENT = buildEventUrl("clipboard-monitor/status")
 export const MONITOR_UPDATE_EVENT = buildEventUrl("clipboard-monitor/update")
 export const ClipboardChangedPayloadSchema = v.object({ value: v.string() })
@@ -48,6 +88,31 @@ export const ClipboardChangedFilesPayloadSchema = v.object({
 })
 export type ClipboardChangedPayload = v.InferOutput<typeof ClipboardChangedPayloadSchema>
 
+/**
+ * Commands reject with this object, `kind` identifies the error so it can be handled without parsing `message`.
+ */
+export type ClipboardErrorKind =
+  | "io"
+  | "emptyClipboard"
+  | "formatUnavailable"
+  | "invalidFileUri"
+  | "imageDecode"
+  | "imageEncode"
+  | "base64Decode"
+  | "backendUnavailable"
+  | "payloadTooLarge"
+  | "historyEntryNotFound"
+  | "transferNotFound"
+  | "invalidRequest"
+  | "lockPoisoned"
+  | "backend"
+export const ClipboardErrorSchema = v.object({ kind: v.string(), message: v.string() })
+export type ClipboardError = { kind: ClipboardErrorKind; message: string }
+
+export function isClipboardError(err: unknown): err is ClipboardError {
+  return v.is(ClipboardErrorSchema, err)
+}
+
 export function hasText() {
   return invoke<boolean>(HAS_TEXT_COMMAND)
 }
@@ -68,6 +133,22 @@ export function hasFiles() {
   return invoke<boolean>(HAS_FILES_COMMAND)
 }
 
+/**
+ * Whether a custom format is on the clipboard.
+ * @param format raw format name, e.g. "application/x-my-app+json"
+ */
+export function hasCustom(format: string) {
+  return invoke<boolean>(HAS_CUSTOM_COMMAND, { format })
+}
+
+/**
+ * Raw names of the formats on the clipboard, as reported by the platform,
+ * e.g. "text/plain" on Linux or "public.utf8-plain-text" on macOS.
+ */
+export function listFormats() {
+  return invoke<string[]>(LIST_FORMATS_COMMAND)
+}
+
 export function writeText(text: string) {
   return invoke<void>(WRITE_TEXT_COMMAND, { text })
 }
@@ -89,6 +170,44 @@ export function writeRtf(rtf: string) {
   return invoke<void>(WRITE_RTF_COMMAND, { rtf })
 }
 
+export function readCustom(format: string): Promise<Uint8Array> {
+  return invoke<ArrayBuffer>(READ_CUSTOM_COMMAND, { format }).then((buffer) => new Uint8Array(buffer))
+}
+
+/**
+ * Replace the clipboard content with bytes in a custom format.
+ * Use write() to offer it next to other formats, e.g. text.
+ */
+export function writeCustom(format: string, bytes: number[] | Uint8Array) {
+  // the length of the UTF-8 format name as a little-endian u32, the name, then the bytes
+  const name = new TextEncoder().encode(format)
+  const body = new Uint8Array(4 + name.length + bytes.length)
+  new DataView(body.buffer).setUint32(0, name.length, true)
+  body.set(name, 4)
+  body.set(bytes, 4 + name.length)
+  return invoke<void>(WRITE_CUSTOM_COMMAND, body)
+}
+
+/** One representation of the content written by write(). */
+export type ClipboardItem =
+  | { type: "text"; text: string }
+  | { type: "html"; html: string }
+  | { type: "rtf"; rtf: string }
+  /** encoded image, e.g. PNG */
+  | { type: "image"; bytes: number[] }
+  /** absolute file paths */
+  | { type: "files"; paths: string[] }
+  /** raw content of a custom format, see writeCustom() */
+  | { type: "custom"; format: string; bytes: number[] }
+
+/**
+ * Write several formats to clipboard in one operation, e.g. an image together with its text
+ * description. Nothing is written if any item is invalid.
+ */
+export function write(items: ClipboardItem[]) {
+  return invoke<void>(WRITE_COMMAND, { items })
+}
+
 export function writeFilesURIs(filesUris: string[]) {
   return invoke<void>(WRITE_FILES_URIS_COMMAND, { filesUris })
 }
@@ -121,39 +240,283 @@ export function readFilesURIs() {
   return invoke<string[]>(READ_FILES_URIS_COMMAND)
 }
 
+export type ImageEncoding = "png" | "jpeg" | "webp" | "bmp"
+
+/** Overrides the `imageEncoding` and `jpegQuality` config of an image read. */
+export type ImageReadOptions = {
+  /** webp is lossless, jpeg drops the alpha channel */
+  encoding?: ImageEncoding
+  /** JPEG quality, from 1 to 100 */
+  quality?: number
+}
+
 /**
  * read clipboard image
  * @returns image in base64 string
  */
-export function readImageBase64() {
-  return invoke<string>(READ_IMAGE_BASE64_COMMAND)
+export function readImageBase64(options?: ImageReadOptions) {
+  return invoke<string>(READ_IMAGE_BASE64_COMMAND, { options })
 }
 
 // export const readImageBase64 = readImage;
 
 /**
  * Read clipboard image, get the data in binary format
- * int_array (Array<number>) is received from Tauri core, Uint8Array and Blob are transformed from int_array
- * @param format data type of returned value, "int_array" is the fastest
+ * An ArrayBuffer is received from Tauri core, Uint8Array and Blob wrap it without copying, int_array copies it
+ * @param format data type of returned value, "Uint8Array" and "Blob" are the fastest
  * @returns
  */
-export function readImageBinary(format: "int_array" | "Uint8Array" | "Blob") {
-  return (
-    invoke<number[] | Uint8Array | Blob>(READ_IMAGE_BINARY_COMMAND) as Promise<number[]>
-  ).then((img_arr: number[]) => {
+export function readImageBinary(
+  format: "int_array" | "Uint8Array" | "Blob",
+  options?: ImageReadOptions
+) {
+  return invoke<ArrayBuffer>(READ_IMAGE_BINARY_COMMAND, { options }).then((buffer) => {
     switch (format) {
       case "int_array":
-        return img_arr
+        return Array.from(new Uint8Array(buffer))
       case "Uint8Array":
-        return new Uint8Array(img_arr)
+        return new Uint8Array(buffer)
       case "Blob":
-        return new Blob([new Uint8Array(img_arr)])
+        return new Blob([buffer])
       default:
-        return img_arr
+        return Array.from(new Uint8Array(buffer))
+    }
+  })
+}
+
+export type ImageInfo = {
+  width: number
+  height: number
+  /** layout of the pixels, e.g. "rgba8" or "rgb16" */
+  colorType: string
+  hasAlpha: boolean
+  bitsPerPixel: number
+  encoding: ImageEncoding
+  /** rough size in bytes of the image read with `encoding`, exact for bmp */
+  estimatedSize: number
+}
+
+/**
+ * Dimensions and pixel layout of the clipboard image, without transferring or encoding it.
+ * Useful to skip huge images before reading them.
+ */
+export function imageInfo(encoding?: ImageEncoding, quality?: number) {
+  return invoke<ImageInfo>(IMAGE_INFO_COMMAND, { encoding, quality })
+}
+
+/** Resampling filter of readImageThumbnail(), from fastest to sharpest. */
+export type ThumbnailFilter = "nearest" | "triangle" | "catmullRom" | "gaussian" | "lanczos3"
+
+export type ImageThumbnail = {
+  /** encoded thumbnail */
+  bytes: Uint8Array
+  width: number
+  height: number
+  /** dimensions of the image on the clipboard */
+  originalWidth: number
+  originalHeight: number
+}
+
+/**
+ * Read clipboard image scaled down to fit in maxWidth x maxHeight, aspect ratio preserved.
+ * Smaller images are not scaled up.
+ */
+export function readImageThumbnail(
+  maxWidth: number,
+  maxHeight: number,
+  encoding?: ImageEncoding,
+  options?: { quality?: number; filter?: ThumbnailFilter }
+): Promise<ImageThumbnail> {
+  // width, height, originalWidth and originalHeight as little-endian u32s, then the encoded thumbnail
+  return invoke<ArrayBuffer>(READ_IMAGE_THUMBNAIL_COMMAND, {
+    maxWidth,
+    maxHeight,
+    encoding,
+    ...options
+  }).then((buffer) => {
+    const header = new DataView(buffer, 0, 16)
+    return {
+      width: header.getUint32(0, true),
+      height: header.getUint32(4, true),
+      originalWidth: header.getUint32(8, true),
+      originalHeight: header.getUint32(12, true),
+      bytes: new Uint8Array(buffer, 16)
+    }
+  })
+}
+
+/** Uncompressed image, 4 bytes per pixel in RGBA order, row by row from the top left. */
+export type RawImage = {
+  width: number
+  height: number
+  rgba: Uint8Array
+}
+
+/**
+ * Read clipboard image as raw RGBA pixels, without encoding it.
+ * Fits e.g. `new ImageData(new Uint8ClampedArray(rgba), width, height)`.
+ */
+export function readImageRgba(): Promise<RawImage> {
+  // width and height as little-endian u32s, then the pixels
+  return invoke<ArrayBuffer>(READ_IMAGE_RGBA_COMMAND).then((buffer) => {
+    const header = new DataView(buffer, 0, 8)
+    return {
+      width: header.getUint32(0, true),
+      height: header.getUint32(4, true),
+      rgba: new Uint8Array(buffer, 8)
+    }
+  })
+}
+
+/**
+ * Write raw RGBA pixels to clipboard, without decoding them.
+ * @param rgba exactly width * height * 4 bytes
+ */
+export function writeImageRgba(width: number, height: number, rgba: Uint8Array | number[]) {
+  const body = new Uint8Array(8 + rgba.length)
+  const header = new DataView(body.buffer, 0, 8)
+  header.setUint32(0, width, true)
+  header.setUint32(4, height, true)
+  body.set(rgba, 8)
+  return invoke<void>(WRITE_IMAGE_RGBA_COMMAND, body)
+}
+
+/** Text formats are transferred as UTF-8, images encoded. */
+export type TransferFormat = "text" | "html" | "rtf" | "image"
+
+export type TransferProgress = {
+  id: number
+  /** bytes transferred so far */
+  transferred: number
+  size: number
+}
+
+export type TransferOptions = {
+  /** maximum size of a chunk in bytes, 1 MiB by default */
+  chunkSize?: number
+  onProgress?: (progress: TransferProgress) => void
+  /** aborting it cancels the transfer, the promise then rejects */
+  signal?: AbortSignal
+}
+
+type TransferEvent =
+  | { event: "started"; data: { id: number; size: number } }
+  | { event: "progress"; data: TransferProgress }
+  | { event: "finished"; data: { id: number } }
+  | { event: "cancelled"; data: { id: number } }
+
+const DEFAULT_CHUNK_SIZE = 1024 * 1024
+
+function abortError(signal: AbortSignal) {
+  return signal.reason ?? new DOMException("The transfer was cancelled", "AbortError")
+}
+
+/**
+ * Read large clipboard contents in chunks, without blocking the IPC bridge with a single huge response.
+ * @param image encoding of an `image` read
+ * @returns the UTF-8 or encoded image bytes
+ */
+export function readChunked(
+  format: TransferFormat,
+  options: TransferOptions & { image?: ImageReadOptions } = {}
+): Promise<Uint8Array> {
+  const { chunkSize, image, onProgress, signal } = options
+  return new Promise((resolve, reject) => {
+    if (signal?.aborted) {
+      reject(abortError(signal))
+      return
+    }
+    let bytes = new Uint8Array(0)
+    let offset = 0
+    // known once `read_chunked` returns, an abort before that cancels as soon as it arrives
+    let id: number | undefined
+    const cancel = () => {
+      if (id !== undefined) invoke<void>(CANCEL_TRANSFER_COMMAND, { id }).catch(() => {})
+    }
+    const onAbort = () => {
+      cancel()
+      reject(abortError(signal!))
     }
+    signal?.addEventListener("abort", onAbort, { once: true })
+    const settle = () => signal?.removeEventListener("abort", onAbort)
+    const onEvent = new Channel<TransferEvent | ArrayBuffer>()
+    onEvent.onmessage = (message) => {
+      // chunks arrive as raw bytes, everything else as JSON events
+      if (message instanceof ArrayBuffer) {
+        bytes.set(new Uint8Array(message), offset)
+        offset += message.byteLength
+        return
+      }
+      switch (message.event) {
+        case "started":
+          bytes = new Uint8Array(message.data.size)
+          break
+        case "progress":
+          onProgress?.(message.data)
+          break
+        case "finished":
+          settle()
+          resolve(bytes)
+          break
+        case "cancelled":
+          settle()
+          reject(signal ? abortError(signal) : new Error("The transfer was cancelled"))
+          break
+      }
+    }
+    invoke<number>(READ_CHUNKED_COMMAND, {
+      format,
+      options: { chunkSize: chunkSize ?? DEFAULT_CHUNK_SIZE, image: image ?? {} },
+      onEvent
+    }).then(
+      (transferId) => {
+        id = transferId
+        if (signal?.aborted) cancel()
+      },
+      (err) => {
+        settle()
+        reject(err)
+      }
+    )
   })
 }
 
+/**
+ * Write large clipboard contents in chunks.
+ * @param bytes UTF-8 for text formats, an encoded image for `image`; strings are encoded as UTF-8
+ */
+export async function writeChunked(
+  format: TransferFormat,
+  bytes: Uint8Array | ArrayBuffer | string,
+  options: TransferOptions = {}
+): Promise<void> {
+  const { chunkSize = DEFAULT_CHUNK_SIZE, onProgress, signal } = options
+  const data =
+    typeof bytes === "string"
+      ? new TextEncoder().encode(bytes)
+      : bytes instanceof ArrayBuffer
+        ? new Uint8Array(bytes)
+        : bytes
+  if (signal?.aborted) throw abortError(signal)
+  const id = await invoke<number>(WRITE_CHUNKED_BEGIN_COMMAND, { format, size: data.length })
+  try {
+    for (let offset = 0; offset < data.length; offset += chunkSize) {
+      if (signal?.aborted) throw abortError(signal)
+      const chunk = data.subarray(offset, offset + chunkSize)
+      // the transfer id as a little-endian u64, then the chunk
+      const body = new Uint8Array(8 + chunk.length)
+      new DataView(body.buffer).setBigUint64(0, BigInt(id), true)
+      body.set(chunk, 8)
+      const progress = await invoke<TransferProgress>(WRITE_CHUNKED_APPEND_COMMAND, body)
+      onProgress?.(progress)
+    }
+  } catch (err) {
+    await invoke<void>(CANCEL_TRANSFER_COMMAND, { id }).catch(() => {})
+    throw err
+  }
+  return invoke<void>(WRITE_CHUNKED_FINISH_COMMAND, { id })
+}
+
 export function convertIntArrToUint8Array(intArr: number[]): Uint8Array {
   return new Uint8Array(intArr)
 }
@@ -164,8 +527,7 @@ export function convertUint8ArrayToBlob(uintArr: Uint8Array): Blob {
 
 /**
  * Here is the transformation flow,
- * read clipboard image as Array<number> (int_array) -> int_array -> Uint8Array -> Blob -> ObjectURL
- * There are many layers which could make this function slow for large images.
+ * read clipboard image as ArrayBuffer -> Blob -> ObjectURL
  * @returns ObjectURL for clipboard image
  */
 export function readImageObjectURL(): Promise<string> {
@@ -183,8 +545,14 @@ export function writeImageBase64(base64: string) {
   return invoke<void>(WRITE_IMAGE_BASE64_COMMAND, { base64Image: base64 })
 }
 
-export function writeImageBinary(bytes: number[]) {
-  return invoke<void>(WRITE_IMAGE_BINARY_COMMAND, { bytes: bytes })
+/**
+ * write image to clipboard
+ * @param bytes encoded image, in any format the image crate decodes (PNG, JPEG, WebP, BMP, ...)
+ */
+export function writeImageBinary(bytes: number[] | Uint8Array | ArrayBuffer) {
+  // sent as the raw request body instead of a JSON array
+  const body = Array.isArray(bytes) ? Uint8Array.from(bytes) : bytes
+  return invoke<void>(WRITE_IMAGE_BINARY_COMMAND, body)
 }
 
 /**
@@ -204,7 +572,7 @@ export function startBruteForceTextMonitor(delay: number = 500) {
     try {
       const text = await readText()
       if (prevText !== text) {
-        await emit(TEXT_CHANGED, { value: text })
+        await emit(prefixed(TEXT_CHANGED), { value: text })
       }
       prevText = text
     } catch (error) {}
@@ -231,7 +599,7 @@ export function startBruteForceImageMonitor(delay: number = 1000) {
     try {
       const img = await readImageBase64()
       if (prevImg !== img) {
-        await emit(IMAGE_CHANGED, { value: img })
+        await emit(prefixed(IMAGE_CHANGED), { value: img })
       }
       prevImg = img
     } catch (error) {
@@ -261,13 +629,95 @@ export type AvailableTypes = {
   files: boolean
 }
 
+/**
+ * Content of every format on the clipboard, missing formats are null.
+ * image is encoded as configured in `imageEncoding` (png by default), in base64.
+ */
+export type ClipboardSnapshot = {
+  text: string | null
+  html: string | null
+  rtf: string | null
+  image: string | null
+  files: string[] | null
+}
+
+/**
+ * Payload of "plugin:clipboard://clipboard-monitor/update".
+ * content is only set when the monitor is started with `includeContent`.
+ */
+export type ClipboardChangeEvent = {
+  sequence: number
+  fingerprint: string
+  available: AvailableTypes
+  content: ClipboardSnapshot | null
+}
+
+export type MonitorOptions = {
+  /** include the clipboard content in every ClipboardChangeEvent */
+  includeContent?: boolean
+  /** only emit once the clipboard has not changed for this many milliseconds */
+  debounceMs?: number
+  /** emit at most one change per this many milliseconds */
+  throttleMs?: number
+  /** emit the last change of a throttle window when it ends instead of dropping it */
+  trailing?: boolean
+}
+
 export function getAvailableTypes(): Promise<AvailableTypes> {
   return invoke<AvailableTypes>(AVAILABLE_TYPES_COMMAND)
 }
 
+export type SnapshotOptions = {
+  /** leave the image out, it is still reported in `available` */
+  skipImage?: boolean
+  /** leave out text, html and rtf larger than this many bytes */
+  maxTextSize?: number
+  /** leave out an encoded image larger than this many bytes */
+  maxImageSize?: number
+}
+
+export type ClipboardContents = {
+  available: AvailableTypes
+  content: ClipboardSnapshot
+}
+
+/**
+ * Read every format on the clipboard at once, so they all come from the same copy.
+ * Formats that fail to read or exceed the size limits are null in `content`.
+ */
+export function readAll(options?: SnapshotOptions): Promise<ClipboardContents> {
+  return invoke<ClipboardContents>(READ_ALL_COMMAND, { options })
+}
+
+/**
+ * Hash of the clipboard content as a hex string, equal fingerprints mean equal content.
+ * While the monitor runs, this is the fingerprint of the last change it saw, so it is cheap to call.
+ */
+export function getFingerprint(): Promise<string> {
+  return invoke<string>(CLIPBOARD_FINGERPRINT_COMMAND)
+}
+
+/**
+ * Per-format events emitted by the clipboard monitor, e.g. "plugin:clipboard://text-changed" for "text".
+ */
+export type FormatEvent = "text" | "html" | "rtf" | "files" | "image" | "imageBinary"
+
+/**
+ * Ask the clipboard monitor to emit the given per-format events.
+ * Subscriptions are counted in Tauri core, each event is emitted once per clipboard update no matter how many windows subscribed.
+ */
+export function subscribeFormatEvents(events: FormatEvent[]) {
+  return invoke<void>(SUBSCRIBE_FORMAT_EVENTS_COMMAND, { events })
+}
+
+export function unsubscribeFormatEvents(events: FormatEvent[]) {
+  return invoke<void>(UNSUBSCRIBE_FORMAT_EVENTS_COMMAND, { events })
+}
+
 /**
- * Listen to "plugin:clipboard://clipboard-monitor/update" from Tauri core.
- * The corresponding clipboard type event will be emitted when there is clipboard update.
+ * Subscribe to the per-format events of the clipboard monitor.
+ * When there is clipboard update, Tauri core emits "plugin:clipboard://something-changed" and the corresponding clipboard type events.
+ * When files are copied, only the files event is emitted.
  * @param listenTypes types of clipboard data to listen to
  * @returns unlisten function
  */
@@ -281,69 +731,28 @@ export function listenToClipboard(
     files: true
   }
 ): Promise<UnlistenFn> {
-  return listen(MONITOR_UPDATE_EVENT, async (e) => {
-    if (e.payload === "clipboard update") {
-      const hasData = await Promise.all([hasFiles(), hasImage(), hasHTML(), hasRTF(), hasText()])
-      const flags: UpdatedTypes = {
-        files: hasData[0],
-        image: hasData[1],
-        imageBinary: hasData[1],
-        html: hasData[2],
-        rtf: hasData[3],
-        text: hasData[4]
-      }
-      await emit(SOMETHING_CHANGED, flags)
-      if (listenTypes.files && flags.files) {
-        const files = await readFiles()
-        if (files && files.length > 0) {
-          await emit(FILES_CHANGED, { value: files })
-        }
-        // flags.files = true;
-        return // ! this return is necessary, copying files also update clipboard text, but we don't want text update to be triggered
-      }
-      if (listenTypes.image && flags.image) {
-        const img = await readImageBase64()
-        if (img) await emit(IMAGE_CHANGED, { value: img })
-        // flags.image = true;
-      }
-      if (listenTypes.imageBinary && flags.imageBinary) {
-        const img = await readImageBinary("int_array")
-        if (img) await emit(IMAGE_BINARY_CHANGED, { value: img })
-        // flags.imageBinary = true;
-      }
-      if (listenTypes.html && flags.html) {
-        await emit(HTML_CHANGED, { value: await readHtml() })
-        // flags.html = true;
-      }
-      if (listenTypes.rtf && flags.rtf) {
-        await emit(RTF_CHANGED, { value: await readRtf() })
-        // flags.rtf = true;
-      }
-      if (listenTypes.text && flags.text) {
-        await emit(TEXT_CHANGED, { value: await readText() })
-        // flags.text = true;
-      }
-      // when clear() is called, this error is thrown, let ignore it
-      // if (!success) {
-      //   throw new Error("Unexpected Error: No proper clipboard type");
-      // }
-    }
+  const events = (Object.keys(listenTypes) as FormatEvent[]).filter(
+    (event) => listenTypes[event]
+  )
+  return subscribeFormatEvents(events).then(() => () => {
+    unsubscribeFormatEvents(events)
   })
 }
 
 /**
  * This listen to clipboard monitor update event, and trigger the callback function.
- * However from this event we don't know whether it's text or image, no real data is returned.
- * Use with listenToClipboard function.
+ * The event tells which formats are available, and contains the content if the monitor was started with `includeContent`.
  * @param cb callback
  * @returns unlisten function
  */
-export function onClipboardUpdate(cb: () => void) {
-  return listen(MONITOR_UPDATE_EVENT, cb)
+export function onClipboardUpdate(cb: (event: ClipboardChangeEvent) => void) {
+  return listen<ClipboardChangeEvent>(prefixed(MONITOR_UPDATE_EVENT), (event) => {
+    cb(event.payload)
+  })
 }
 
 export async function onTextUpdate(cb: (text: string) => void): Promise<UnlistenFn> {
-  return await listen(TEXT_CHANGED, (event) => {
+  return await listen(prefixed(TEXT_CHANGED), (event) => {
     const text = v.parse(ClipboardChangedPayloadSchema, event.payload).value
     cb(text)
   })
@@ -352,48 +761,48 @@ export async function onTextUpdate(cb: (text: string) => void): Promise<Unlisten
 /**
  * Listen to clipboard update event and get the updated types in a callback.
  * This listener tells you what types of data are updated.
- * This relies on `listenToClipboard()` who emits events this function listens to.
+ * Tauri core only emits this event while `listenToClipboard()` subscriptions are active.
  * You can run `listenToClipboard()` or `startListening()` before calling this function.
  * When HTML is copied, this will be passed to callback: {files: false, image: false, html: true, rtf: false, text: true}
  * @param cb
  * @returns
  */
 export function onSomethingUpdate(cb: (updatedTypes: UpdatedTypes) => void) {
-  return listen(SOMETHING_CHANGED, (event) => {
+  return listen(prefixed(SOMETHING_CHANGED), (event) => {
     cb(event.payload as UpdatedTypes)
   })
 }
 
 export function onHTMLUpdate(cb: (text: string) => void): Promise<UnlistenFn> {
-  return listen(HTML_CHANGED, (event) => {
+  return listen(prefixed(HTML_CHANGED), (event) => {
     const text = v.parse(ClipboardChangedPayloadSchema, event.payload).value
     cb(text)
   })
 }
 
 export function onRTFUpdate(cb: (text: string) => void): Promise<UnlistenFn> {
-  return listen(RTF_CHANGED, (event) => {
+  return listen(prefixed(RTF_CHANGED), (event) => {
     const text = v.parse(ClipboardChangedPayloadSchema, event.payload).value
     cb(text)
   })
 }
 
 export function onFilesUpdate(cb: (files: string[]) => void): Promise<UnlistenFn> {
-  return listen(FILES_CHANGED, (event) => {
+  return listen(prefixed(FILES_CHANGED), (event) => {
     const files = v.parse(ClipboardChangedFilesPayloadSchema, event.payload).value
     cb(files)
   })
 }
 
 export function onImageUpdate(cb: (base64ImageStr: string) => void): Promise<UnlistenFn> {
-  return listen(IMAGE_CHANGED, (event) => {
+  return listen(prefixed(IMAGE_CHANGED), (event) => {
     const base64ImageStr = v.parse(ClipboardChangedPayloadSchema, event.payload).value
     cb(base64ImageStr)
   })
 }
 
 export function onImageBinaryUpdate(cb: (image: number[]) => void) {
-  return listen(IMAGE_BINARY_CHANGED, (event) => {
+  return listen(prefixed(IMAGE_BINARY_CHANGED), (event) => {
     cb(v.parse(ClipboardBinaryChangedPayloadSchema, event.payload).value)
   })
 }
@@ -415,9 +824,10 @@ export function isMonitorRunning() {
  * After monitor is started, events "plugin:clipboard://clipboard-monitor/update" will be emitted when there is clipboard update.
  * "plugin:clipboard://clipboard-monitor/status" event is also emitted when monitor status updates
  * Still have to listen to these events.
+ * Calling it again while the monitor runs restarts it if the options differ.
  */
-export function startMonitor() {
-  return invoke<void>(START_MONITOR_COMMAND)
+export function startMonitor(options?: MonitorOptions) {
+  return invoke<void>(START_MONITOR_COMMAND, { options })
 }
 
 /**
@@ -434,7 +844,7 @@ export function stopMonitor() {
 export async function listenToMonitorStatusUpdate(
   cb: (running: boolean) => void
 ): Promise<UnlistenFn> {
-  return await listen(CLIPBOARD_MONITOR_STATUS_UPDATE_EVENT, (event) => {
+  return await listen(prefixed(CLIPBOARD_MONITOR_STATUS_UPDATE_EVENT), (event) => {
     const newStatus = v.parse(v.boolean(), event.payload)
     cb(newStatus)
   })
@@ -448,9 +858,10 @@ export function startListening(
     image: true,
     imageBinary: false,
     files: true
-  }
+  },
+  options?: MonitorOptions
 ): Promise<() => Promise<void>> {
-  return startMonitor()
+  return startMonitor(options)
     .then(() => listenToClipboard(listenTypes))
     .then((unlistenClipboard) => {
       // return an unlisten function that stop listening to clipboard update and stop the monitor
@@ -460,3 +871,115 @@ export function startListening(
       }
     })
 }
+
+export type HistoryEntry = {
+  id: number
+  /** milliseconds since the Unix epoch */
+  timestamp: number
+  /** fingerprint of the content, see getFingerprint() */
+  hash: string
+  available: AvailableTypes
+  content: ClipboardSnapshot
+  /** size in bytes of the captured payloads, counted against the retention limits */
+  size: number
+  /** pinned entries are never evicted and survive historyClear() */
+  pinned: boolean
+  tags: string[]
+}
+
+export type HistoryChangedEvent = {
+  added: number[]
+  removed: number[]
+  /** entries whose pin state or tags changed */
+  updated: number[]
+}
+
+export type HistoryFormat = "text" | "html" | "rtf" | "image" | "files"
+
+/** unset criteria match every entry */
+export type HistoryFilter = {
+  tag?: string
+  pinned?: boolean
+  format?: HistoryFormat
+}
+
+/**
+ * Clipboard changes recorded by the monitor, newest first.
+ * History is only recorded when `history.enabled` is set in the plugin config.
+ * When the history is persisted, payloads larger than `history.blobThreshold` are left out, use historyGet() to read them.
+ */
+export function historyList(filter?: HistoryFilter) {
+  return invoke<HistoryEntry[]>(HISTORY_LIST_COMMAND, { filter })
+}
+
+export function historyGet(id: number) {
+  return invoke<HistoryEntry>(HISTORY_GET_COMMAND, { id })
+}
+
+export function historyDelete(id: number) {
+  return invoke<void>(HISTORY_DELETE_COMMAND, { id })
+}
+
+/**
+ * Write every format captured with an entry back to the clipboard at once.
+ * The restored content is not recorded as a new history entry.
+ */
+export function historyRestore(id: number) {
+  return invoke<void>(HISTORY_RESTORE_COMMAND, { id })
+}
+
+export function historyPin(id: number) {
+  return invoke<void>(HISTORY_PIN_COMMAND, { id })
+}
+
+export function historyUnpin(id: number) {
+  return invoke<void>(HISTORY_UNPIN_COMMAND, { id })
+}
+
+/** replace the tags of an entry */
+export function historySetTags(id: number, tags: string[]) {
+  return invoke<void>(HISTORY_SET_TAGS_COMMAND, { id, tags })
+}
+
+/** remove every entry that is not pinned */
+export function historyClear() {
+  return invoke<void>(HISTORY_CLEAR_COMMAND)
+}
+
+export function onHistoryChanged(cb: (event: HistoryChangedEvent) => void) {
+  return listen<HistoryChangedEvent>(prefixed(HISTORY_CHANGED_EVENT), (event) => {
+    cb(event.payload)
+  })
+}
+
+export type SearchField = "text" | "html" | "rtf" | "files"
+
+export type Highlight = {
+  field: SearchField
+  /** excerpt of the field around the first match */
+  snippet: string
+  /** [start, end) of every match in snippet, usable with snippet.slice() */
+  ranges: [number, number][]
+}
+
+export type HistorySearchHit = {
+  entry: HistoryEntry
+  score: number
+  highlights: Highlight[]
+}
+
+export type HistorySearchResults = {
+  /** number of matching entries across all pages */
+  total: number
+  hits: HistorySearchHit[]
+}
+
+/**
+ * Search the text, HTML, RTF and file paths of the history.
+ * Every word of the query must match a word of the entry or the start of one.
+ * @param offset number of hits to skip
+ * @param limit maximum number of hits returned, 20 by default
+ */
+export function historySearch(query: string, offset?: number, limit?: number) {
+  return invoke<HistorySearchResults>(HISTORY_SEARCH_COMMAND, { query, offset, limit })
+}
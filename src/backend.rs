@@ -0,0 +1,154 @@
+use clipboard_rs::{
+    Clipboard as ClipboardRS, ClipboardContent, ClipboardContext as ClipboardRsContext,
+    ClipboardHandler, ClipboardWatcher, ClipboardWatcherContext, ContentFormat, Result,
+    RustImageData,
+};
+
+/// The clipboard operations used by [`crate::Clipboard`].
+///
+/// `clipboard_rs::ClipboardContext` is the default implementation. A custom backend can be
+/// passed to [`crate::Builder::backend`] to work around platform issues or to run without a
+/// display server.
+pub trait ClipboardBackend: Send {
+    /// Raw names of the formats on the clipboard, as reported by the platform.
+    fn available_formats(&self) -> Result<Vec<String>>;
+
+    fn has(&self, format: ContentFormat) -> bool;
+
+    /// Raw content of `format`, e.g. a custom MIME type like `application/x-my-app+json`.
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>>;
+
+    fn get_text(&self) -> Result<String>;
+
+    fn get_html(&self) -> Result<String>;
+
+    fn get_rich_text(&self) -> Result<String>;
+
+    fn get_image(&self) -> Result<RustImageData>;
+
+    fn get_files(&self) -> Result<Vec<String>>;
+
+    fn set_text(&self, text: String) -> Result<()>;
+
+    fn set_html(&self, html: String) -> Result<()>;
+
+    fn set_rich_text(&self, rtf: String) -> Result<()>;
+
+    fn set_image(&self, image: RustImageData) -> Result<()>;
+
+    fn set_files(&self, files: Vec<String>) -> Result<()>;
+
+    fn set_buffer(&self, format: &str, buffer: Vec<u8>) -> Result<()>;
+
+    /// Write several formats at once, replacing the current clipboard content.
+    fn set(&self, contents: Vec<ClipboardContent>) -> Result<()>;
+
+    fn clear(&self) -> Result<()>;
+
+    /// Start watching the clipboard on a background thread, calling `handler` on every change.
+    /// Watching stops when the returned [`WatchHandle`] is stopped or dropped.
+    fn watch(&self, handler: Box<dyn ClipboardHandler + Send>) -> Result<WatchHandle>;
+}
+
+/// Keeps a clipboard watcher running. Dropping it stops the watcher.
+pub struct WatchHandle {
+    _guard: Box<dyn Send>,
+}
+
+impl WatchHandle {
+    /// `guard` is dropped when the watcher should stop.
+    pub fn new(guard: impl Send + 'static) -> Self {
+        Self {
+            _guard: Box::new(guard),
+        }
+    }
+
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+struct BoxedHandler(Box<dyn ClipboardHandler + Send>);
+
+impl ClipboardHandler for BoxedHandler {
+    fn on_clipboard_change(&mut self) {
+        self.0.on_clipboard_change();
+    }
+}
+
+impl ClipboardBackend for ClipboardRsContext {
+    fn available_formats(&self) -> Result<Vec<String>> {
+        ClipboardRS::available_formats(self)
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        ClipboardRS::has(self, format)
+    }
+
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>> {
+        ClipboardRS::get_buffer(self, format)
+    }
+
+    fn get_text(&self) -> Result<String> {
+        ClipboardRS::get_text(self)
+    }
+
+    fn get_html(&self) -> Result<String> {
+        ClipboardRS::get_html(self)
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        ClipboardRS::get_rich_text(self)
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        ClipboardRS::get_image(self)
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        ClipboardRS::get_files(self)
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        ClipboardRS::set_text(self, text)
+    }
+
+    fn set_html(&self, html: String) -> Result<()> {
+        ClipboardRS::set_html(self, html)
+    }
+
+    fn set_rich_text(&self, rtf: String) -> Result<()> {
+        ClipboardRS::set_rich_text(self, rtf)
+    }
+
+    fn set_image(&self, image: RustImageData) -> Result<()> {
+        ClipboardRS::set_image(self, image)
+    }
+
+    fn set_files(&self, files: Vec<String>) -> Result<()> {
+        ClipboardRS::set_files(self, files)
+    }
+
+    fn set_buffer(&self, format: &str, buffer: Vec<u8>) -> Result<()> {
+        ClipboardRS::set_buffer(self, format, buffer)
+    }
+
+    fn set(&self, contents: Vec<ClipboardContent>) -> Result<()> {
+        ClipboardRS::set(self, contents)
+    }
+
+    fn clear(&self) -> Result<()> {
+        ClipboardRS::clear(self)
+    }
+
+    fn watch(&self, handler: Box<dyn ClipboardHandler + Send>) -> Result<WatchHandle> {
+        let mut watcher: ClipboardWatcherContext<BoxedHandler> = ClipboardWatcherContext::new()?;
+        let shutdown = watcher
+            .add_handler(BoxedHandler(handler))
+            .get_shutdown_channel();
+        std::thread::spawn(move || {
+            watcher.start_watch();
+        });
+        Ok(WatchHandle::new(shutdown))
+    }
+}
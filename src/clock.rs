@@ -0,0 +1,28 @@
+use std::time::{Instant, SystemTime};
+
+/// Source of time for the clipboard monitor and the history, replaceable in tests with
+/// `MockClock`.
+pub trait Clock: Send + Sync {
+    /// Monotonic time, used for the monitor's debounce and throttle windows.
+    fn now(&self) -> Instant;
+    /// Wall clock time, used for history timestamps and retention.
+    fn system_time(&self) -> SystemTime;
+    /// Run `listener` whenever the clock is moved by hand, until it returns `false`. The
+    /// monitor uses it to emit delayed changes as soon as they are due. Real clocks move on
+    /// their own and never call it.
+    fn on_advance(&self, _listener: Box<dyn Fn() -> bool + Send + Sync>) {}
+}
+
+/// The real clocks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
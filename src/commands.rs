@@ -1,11 +1,18 @@
-use crate::Clipboard;
-use tauri::{command, AppHandle, Runtime, State, Window};
+use crate::{
+    ChunkedReadOptions, Clipboard, ClipboardContents, ClipboardItem, Error, FormatEvent,
+    HistoryEntry, HistoryFilter, HistorySearchResults, ImageEncoding, ImageInfo, ImageReadOptions,
+    MonitorOptions, RawImage, Result, SnapshotOptions, ThumbnailFilter, TransferFormat,
+    TransferProgress,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use tauri::{
+    command,
+    ipc::{Channel, InvokeBody, Request, Response},
+    AppHandle, Runtime, State, Window,
+};
 
 #[command]
-pub fn has_text<R: Runtime>(
-    _app: AppHandle<R>,
-    clipboard: State<'_, Clipboard>,
-) -> Result<bool, String> {
+pub fn has_text<R: Runtime>(_app: AppHandle<R>, clipboard: State<'_, Clipboard>) -> Result<bool> {
     clipboard.has_text()
 }
 
@@ -14,7 +21,7 @@ pub fn has_image<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<bool, String> {
+) -> Result<bool> {
     clipboard.has_image()
 }
 
@@ -23,7 +30,7 @@ pub fn has_html<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<bool, String> {
+) -> Result<bool> {
     clipboard.has_html()
 }
 
@@ -32,7 +39,7 @@ pub fn has_rtf<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<bool, String> {
+) -> Result<bool> {
     clipboard.has_rtf()
 }
 
@@ -41,23 +48,50 @@ pub fn has_files<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<bool, String> {
+) -> Result<bool> {
     clipboard.has_files()
 }
 
 #[command]
-pub fn available_types(
+pub fn has_custom<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    format: String,
+) -> Result<bool> {
+    clipboard.has_custom(format)
+}
+
+#[command]
+pub fn list_formats<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<crate::desktop::AvailableTypes, String> {
+) -> Result<Vec<String>> {
+    clipboard.list_formats()
+}
+
+#[command]
+pub fn available_types(clipboard: State<'_, Clipboard>) -> Result<crate::AvailableTypes> {
     clipboard.available_types()
 }
 
+#[command]
+pub async fn read_all<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    options: Option<SnapshotOptions>,
+) -> Result<ClipboardContents> {
+    clipboard.read_all(&options.unwrap_or_default())
+}
+
 #[command]
 pub fn read_text<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<String, String> {
+) -> Result<String> {
     clipboard.read_text()
 }
 
@@ -66,7 +100,7 @@ pub fn read_html<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<String, String> {
+) -> Result<String> {
     clipboard.read_html()
 }
 
@@ -75,16 +109,27 @@ pub fn read_rtf<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<String, String> {
+) -> Result<String> {
     clipboard.read_rtf()
 }
 
+/// read the custom `format`, returned as the raw response body
+#[command]
+pub async fn read_custom<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    format: String,
+) -> Result<Response> {
+    Ok(Response::new(clipboard.read_custom(format)?))
+}
+
 #[command]
 pub fn read_files<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>> {
     clipboard.read_files()
 }
 
@@ -93,7 +138,7 @@ pub fn read_files_uris<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>> {
     clipboard.read_files_uris()
 }
 
@@ -103,7 +148,7 @@ pub fn write_files_uris<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     files_uris: Vec<String>,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_files_uris(files_uris)
 }
 
@@ -113,30 +158,8 @@ pub fn write_files<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     files_paths: Vec<String>,
-) -> Result<(), String> {
-    for file in &files_paths {
-        if file.starts_with("file://") {
-            return Err(format!(
-                "Invalid file uri: {}. File uri should not start with file://",
-                file
-            ));
-        }
-    }
-    let mut files_uris: Vec<String> = vec![];
-    #[cfg(any(target_os = "linux", target_os = "macos"))]
-    {
-        for file in &files_paths {
-            files_uris.push(format!("file://{}", file))
-        }
-    }
-
-    #[cfg(target_os = "windows")]
-    {
-        for file in &files_paths {
-            files_uris.push(file.clone())
-        }
-    }
-    write_files_uris(_app, _window, clipboard, files_uris)
+) -> Result<()> {
+    clipboard.write_files(files_paths)
 }
 
 #[command]
@@ -145,7 +168,7 @@ pub fn write_text<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     text: String,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_text(text)
 }
 
@@ -155,7 +178,7 @@ pub fn write_html<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     html: String,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_html(html)
 }
 
@@ -166,7 +189,7 @@ pub fn write_html_and_text<R: Runtime>(
     clipboard: State<'_, Clipboard>,
     html: String,
     text: String,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_html_and_text(html, text)
 }
 
@@ -176,18 +199,67 @@ pub fn write_rtf<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     rtf: String,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_rtf(rtf)
 }
 
+/// write bytes in a custom format, sent as the raw request body prefixed with the length of the
+/// UTF-8 format name as a little-endian `u32` and the name, or as `{ format, bytes }`
+#[command]
+pub async fn write_custom<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    request: Request<'_>,
+) -> Result<()> {
+    #[derive(Deserialize)]
+    struct Args {
+        format: String,
+        bytes: Vec<u8>,
+    }
+    let args = match request.body() {
+        InvokeBody::Raw(body) => {
+            let len = body
+                .get(..4)
+                .and_then(|len| len.try_into().ok())
+                .map(u32::from_le_bytes)
+                .ok_or_else(|| {
+                    Error::InvalidRequest("expected a 4 byte format name length".to_string())
+                })? as usize;
+            let format = body
+                .get(4..)
+                .and_then(|rest| rest.get(..len))
+                .and_then(|format| std::str::from_utf8(format).ok())
+                .ok_or_else(|| Error::InvalidRequest("expected a UTF-8 format name".to_string()))?;
+            Args {
+                format: format.to_string(),
+                bytes: body[4 + len..].to_vec(),
+            }
+        }
+        InvokeBody::Json(json) => json_args(json)?,
+    };
+    clipboard.write_custom(args.format, args.bytes)
+}
+
+#[command]
+pub async fn write<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    items: Vec<ClipboardItem>,
+) -> Result<()> {
+    clipboard.write(items)
+}
+
 /// read image from clipboard and return a base64 string
 #[command]
 pub async fn read_image_base64<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<String, String> {
-    clipboard.read_image_base64()
+    options: Option<ImageReadOptions>,
+) -> Result<String> {
+    clipboard.read_image_base64_with_options(options.unwrap_or_default())
 }
 
 #[command]
@@ -195,8 +267,54 @@ pub async fn read_image_binary<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<Vec<u8>, String> {
-    clipboard.read_image_binary()
+    options: Option<ImageReadOptions>,
+) -> Result<Response> {
+    Ok(Response::new(clipboard.read_image_binary_with_options(
+        options.unwrap_or_default(),
+    )?))
+}
+
+/// dimensions and pixel layout of the clipboard image, without transferring it
+#[command]
+pub async fn image_info<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    encoding: Option<ImageEncoding>,
+    quality: Option<u8>,
+) -> Result<ImageInfo> {
+    clipboard.image_info(ImageReadOptions { encoding, quality })
+}
+
+/// read image from clipboard, scaled down to fit in `max_width` x `max_height`, see
+/// [`crate::ImageThumbnail::to_bytes`] for the response layout
+#[command]
+pub async fn read_image_thumbnail<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    max_width: u32,
+    max_height: u32,
+    encoding: Option<ImageEncoding>,
+    quality: Option<u8>,
+    filter: Option<ThumbnailFilter>,
+) -> Result<Response> {
+    let thumbnail = clipboard.read_image_thumbnail(
+        max_width,
+        max_height,
+        ImageReadOptions { encoding, quality },
+        filter.unwrap_or_default(),
+    )?;
+    Ok(Response::new(thumbnail.to_bytes()))
+}
+
+/// read image from clipboard as raw RGBA8 pixels, see [`RawImage::to_bytes`] for the response
+/// layout
+#[command]
+pub async fn read_image_rgba<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+) -> Result<Response> {
+    Ok(Response::new(clipboard.read_image_rgba()?.to_bytes()))
 }
 
 /// write base64 image to clipboard
@@ -206,26 +324,134 @@ pub async fn write_image_base64<R: Runtime>(
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
     base64_image: String,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.write_image_base64(base64_image)
 }
 
+/// write an encoded image to clipboard, sent as the raw request body or as `{ bytes }`
 #[command]
 pub async fn write_image_binary<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-    bytes: Vec<u8>,
-) -> Result<(), String> {
+    request: Request<'_>,
+) -> Result<()> {
+    #[derive(Deserialize)]
+    struct Args {
+        bytes: Vec<u8>,
+    }
+    let bytes = match request.body() {
+        InvokeBody::Raw(bytes) => bytes.clone(),
+        InvokeBody::Json(json) => json_args::<Args>(json)?.bytes,
+    };
     clipboard.write_image_binary(bytes)
 }
 
+/// write raw RGBA8 pixels to clipboard, sent as the raw request body in the layout of
+/// [`RawImage::to_bytes`] or as `{ width, height, rgba }`
+#[command]
+pub async fn write_image_rgba<R: Runtime>(
+    _app: AppHandle<R>,
+    _window: Window<R>,
+    clipboard: State<'_, Clipboard>,
+    request: Request<'_>,
+) -> Result<()> {
+    let image = match request.body() {
+        InvokeBody::Raw(bytes) => RawImage::from_bytes(bytes.clone())?,
+        InvokeBody::Json(json) => json_args(json)?,
+    };
+    clipboard.write_image_rgba(image)
+}
+
+fn json_args<T: DeserializeOwned>(json: &serde_json::Value) -> Result<T> {
+    T::deserialize(json).map_err(|err| Error::InvalidRequest(err.to_string()))
+}
+
+/// read `format` in chunks sent through `on_event`, see `Clipboard::read_chunked`
+#[command]
+pub async fn read_chunked<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    format: TransferFormat,
+    options: Option<ChunkedReadOptions>,
+    on_event: Channel,
+) -> Result<u64> {
+    clipboard.read_chunked(format, options.unwrap_or_default(), on_event)
+}
+
+#[command]
+pub fn write_chunked_begin<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    format: TransferFormat,
+    size: u64,
+) -> Result<u64> {
+    clipboard.write_chunked_begin(format, size)
+}
+
+/// append a chunk to a chunked write, sent as the raw request body prefixed with the transfer id
+/// as a little-endian `u64`, or as `{ id, bytes }`
+#[command]
+pub async fn write_chunked_append<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    request: Request<'_>,
+) -> Result<TransferProgress> {
+    #[derive(Deserialize)]
+    struct Args {
+        id: u64,
+        bytes: Vec<u8>,
+    }
+    match request.body() {
+        InvokeBody::Raw(bytes) => {
+            let id = bytes
+                .get(..8)
+                .and_then(|id| id.try_into().ok())
+                .map(u64::from_le_bytes)
+                .ok_or_else(|| {
+                    Error::InvalidRequest("expected an 8 byte transfer id".to_string())
+                })?;
+            clipboard.write_chunked_append(id, &bytes[8..])
+        }
+        InvokeBody::Json(json) => {
+            let args = json_args::<Args>(json)?;
+            clipboard.write_chunked_append(args.id, &args.bytes)
+        }
+    }
+}
+
+#[command]
+pub async fn write_chunked_finish<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.write_chunked_finish(id)
+}
+
+#[command]
+pub fn cancel_transfer<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.cancel_transfer(id)
+}
+
+#[command]
+pub fn clipboard_fingerprint<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+) -> Result<String> {
+    clipboard.fingerprint()
+}
+
 #[command]
 pub fn clear<R: Runtime>(
     _app: AppHandle<R>,
     _window: Window<R>,
     clipboard: State<'_, Clipboard>,
-) -> Result<(), String> {
+) -> Result<()> {
     clipboard.clear()
 }
 
@@ -233,15 +459,16 @@ pub fn clear<R: Runtime>(
 pub async fn start_monitor<R: Runtime>(
     app: tauri::AppHandle<R>,
     state: tauri::State<'_, Clipboard>,
-) -> Result<(), String> {
-    state.start_monitor(app)
+    options: Option<MonitorOptions>,
+) -> Result<()> {
+    state.start_monitor_with_options(app, options.unwrap_or_default())
 }
 
 #[command]
 pub async fn stop_monitor<R: Runtime>(
     app: tauri::AppHandle<R>,
     state: tauri::State<'_, Clipboard>,
-) -> Result<(), String> {
+) -> Result<()> {
     state.stop_monitor(app)
 }
 
@@ -249,6 +476,107 @@ pub async fn stop_monitor<R: Runtime>(
 pub fn is_monitor_running<R: Runtime>(
     _app: tauri::AppHandle<R>,
     state: tauri::State<'_, Clipboard>,
-) -> bool {
+) -> Result<bool> {
     state.is_monitor_running()
 }
+
+#[command]
+pub fn subscribe_format_events<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    state: tauri::State<'_, Clipboard>,
+    events: Vec<FormatEvent>,
+) -> Result<()> {
+    state.subscribe_format_events(&events)
+}
+
+#[command]
+pub fn unsubscribe_format_events<R: Runtime>(
+    _app: tauri::AppHandle<R>,
+    state: tauri::State<'_, Clipboard>,
+    events: Vec<FormatEvent>,
+) -> Result<()> {
+    state.unsubscribe_format_events(&events)
+}
+
+#[command]
+pub async fn history_list<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    filter: Option<HistoryFilter>,
+) -> Result<Vec<HistoryEntry>> {
+    clipboard.history_list(&filter.unwrap_or_default())
+}
+
+#[command]
+pub async fn history_get<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<HistoryEntry> {
+    clipboard.history_get(id)
+}
+
+#[command]
+pub async fn history_delete<R: Runtime>(
+    app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.history_delete(app, id)
+}
+
+#[command]
+pub async fn history_clear<R: Runtime>(
+    app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+) -> Result<()> {
+    clipboard.history_clear(app)
+}
+
+#[command]
+pub async fn history_search<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<HistorySearchResults> {
+    clipboard.history_search(&query, offset.unwrap_or(0), limit.unwrap_or(20))
+}
+
+#[command]
+pub async fn history_pin<R: Runtime>(
+    app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.history_pin(app, id)
+}
+
+#[command]
+pub async fn history_unpin<R: Runtime>(
+    app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.history_unpin(app, id)
+}
+
+#[command]
+pub async fn history_set_tags<R: Runtime>(
+    app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+    tags: Vec<String>,
+) -> Result<()> {
+    clipboard.history_set_tags(app, id, tags)
+}
+
+#[command]
+pub async fn history_restore<R: Runtime>(
+    _app: AppHandle<R>,
+    clipboard: State<'_, Clipboard>,
+    id: u64,
+) -> Result<()> {
+    clipboard.history_restore(id)
+}
@@ -0,0 +1,167 @@
+use serde::Deserialize;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use crate::{HistoryFormat, ImageEncoding};
+
+/// Plugin configuration, read from `plugins.clipboard` in `tauri.conf.json`.
+/// Options set on [`crate::Builder`] take precedence over the JSON config.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Config {
+    /// Start the clipboard monitor when the plugin is initialized.
+    pub auto_start_monitor: bool,
+    /// Encoding of images returned by `read_image_binary` and `read_image_base64`.
+    pub image_encoding: ImageEncoding,
+    /// Quality of JPEG images, from 1 to 100.
+    pub jpeg_quality: u8,
+    /// Maximum size in bytes of text, html and rtf payloads read or written.
+    pub max_text_size: Option<usize>,
+    /// Maximum size in bytes of encoded images and custom format payloads read or written.
+    pub max_image_size: Option<usize>,
+    pub history: HistoryConfig,
+    /// Prefix of every event emitted by the plugin.
+    pub event_prefix: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            auto_start_monitor: false,
+            image_encoding: ImageEncoding::default(),
+            jpeg_quality: 75,
+            max_text_size: None,
+            max_image_size: None,
+            history: HistoryConfig::default(),
+            event_prefix: "plugin:clipboard://".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Full name of the event `name`, e.g. `plugin:clipboard://clipboard-monitor/update`.
+    pub fn event_name(&self, name: &str) -> String {
+        format!("{}{}", self.event_prefix, name)
+    }
+}
+
+/// Clipboard history settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryConfig {
+    /// Record clipboard changes seen by the monitor.
+    pub enabled: bool,
+    /// Number of entries kept, older entries are evicted first.
+    pub max_entries: usize,
+    /// Keep the history on disk across restarts.
+    pub persist: bool,
+    /// Directory of the persisted history. Relative paths are resolved against the app data
+    /// directory. Defaults to `clipboard-history` in the app data directory.
+    pub directory: Option<PathBuf>,
+    /// Payloads larger than this many bytes are stored as separate blob files.
+    pub blob_threshold: usize,
+    /// The history log is compacted once it grows past this many bytes.
+    pub compact_threshold: u64,
+    pub retention: RetentionPolicy,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 100,
+            persist: false,
+            directory: None,
+            blob_threshold: 64 * 1024,
+            compact_threshold: 4 * 1024 * 1024,
+            retention: RetentionPolicy::default(),
+        }
+    }
+}
+
+/// Limits on how long and how much history is kept, on top of `HistoryConfig::max_entries`.
+/// Pinned entries are exempt. The oldest entries are evicted first.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionPolicy {
+    /// Entries older than this many seconds are evicted.
+    pub max_age_secs: Option<u64>,
+    /// Maximum size in bytes of the payloads of all entries.
+    pub max_total_bytes: Option<u64>,
+    /// Limits on the entries having a format.
+    pub formats: HashMap<HistoryFormat, FormatRetention>,
+    /// How often the background sweeper enforces the limits, in milliseconds. Values below one
+    /// second are raised to one second.
+    pub sweep_interval_ms: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_secs: None,
+            max_total_bytes: None,
+            formats: HashMap::new(),
+            sweep_interval_ms: 60_000,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Whether any limit is set.
+    pub fn has_limits(&self) -> bool {
+        self.max_age_secs.is_some() || self.max_total_bytes.is_some() || !self.formats.is_empty()
+    }
+
+    /// Interval of the background sweeper, `sweep_interval_ms` but at least one second so the
+    /// sweeper cannot hog the history lock.
+    pub fn sweep_interval(&self) -> Duration {
+        Duration::from_millis(self.sweep_interval_ms.max(1000))
+    }
+}
+
+/// Retention limits of the entries having one format.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatRetention {
+    pub max_age_secs: Option<u64>,
+    pub max_entries: Option<usize>,
+    /// Maximum size in bytes of the payloads of these entries.
+    pub max_total_bytes: Option<u64>,
+}
+
+/// Options set on [`crate::Builder`], applied on top of the JSON config.
+#[derive(Debug, Default)]
+pub(crate) struct ConfigOverrides {
+    pub auto_start_monitor: Option<bool>,
+    pub image_encoding: Option<ImageEncoding>,
+    pub jpeg_quality: Option<u8>,
+    pub max_text_size: Option<usize>,
+    pub max_image_size: Option<usize>,
+    pub history: Option<HistoryConfig>,
+    pub event_prefix: Option<String>,
+}
+
+impl ConfigOverrides {
+    pub fn apply(self, config: &mut Config) {
+        if let Some(auto_start_monitor) = self.auto_start_monitor {
+            config.auto_start_monitor = auto_start_monitor;
+        }
+        if let Some(image_encoding) = self.image_encoding {
+            config.image_encoding = image_encoding;
+        }
+        if let Some(jpeg_quality) = self.jpeg_quality {
+            config.jpeg_quality = jpeg_quality;
+        }
+        if let Some(max_text_size) = self.max_text_size {
+            config.max_text_size = Some(max_text_size);
+        }
+        if let Some(max_image_size) = self.max_image_size {
+            config.max_image_size = Some(max_image_size);
+        }
+        if let Some(history) = self.history {
+            config.history = history;
+        }
+        if let Some(event_prefix) = self.event_prefix {
+            config.event_prefix = event_prefix;
+        }
+    }
+}
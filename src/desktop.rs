@@ -1,22 +1,45 @@
 use base64::{engine::general_purpose, Engine as _};
 use clipboard_rs::{
-    common::RustImage, Clipboard as ClipboardRS, ClipboardContent,
-    ClipboardContext as ClipboardRsContext, ClipboardHandler, ClipboardWatcher,
-    ClipboardWatcherContext, ContentFormat, RustImageData, WatcherShutdown,
+    common::RustImage, ClipboardContent, ClipboardContext as ClipboardRsContext, ContentFormat,
+    RustImageData,
 };
-use image::EncodableLayout;
+use image::codecs::{bmp::BmpEncoder, jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder};
+use image::{imageops::FilterType, ColorType};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use tauri::{plugin::PluginApi, AppHandle, Emitter, Runtime};
-
-pub fn init<R: Runtime, C: DeserializeOwned>(_api: PluginApi<R, C>) -> crate::Result<Clipboard> {
-    Ok(Clipboard {
-        clipboard: Arc::new(Mutex::new(ClipboardRsContext::new().unwrap())),
-        watcher_shutdown: Arc::default(),
-    })
+use std::sync::{atomic::Ordering, Arc, Mutex};
+use tauri::{ipc::Channel, plugin::PluginApi, AppHandle, Emitter, Runtime};
+
+use crate::backend::{ClipboardBackend, WatchHandle};
+use crate::history::{spawn_sweeper, History, HistoryChangedEvent, HistoryEntry, HistoryFilter};
+use crate::monitor::{
+    fingerprint, format_fingerprint, ClipboardChangeEvent, ClipboardContents, ClipboardMonitor,
+    ClipboardSnapshot, FormatEvent, MonitorOptions, MonitorState, SnapshotOptions,
+};
+use crate::search::HistorySearchResults;
+use crate::subscription::{ClipboardWatch, Subscription, WatchQueue};
+use crate::transfer::{
+    ChunkedReadOptions, TransferFormat, TransferProgress, Transfers, DEFAULT_CHUNK_SIZE,
+};
+use crate::{
+    ClipboardItem, Clock, Config, Error, ImageEncoding, ImageInfo, ImageReadOptions,
+    ImageThumbnail, RawImage, Result, SystemClock, ThumbnailFilter,
+};
+
+pub fn init<R: Runtime, C: DeserializeOwned>(
+    _api: PluginApi<R, C>,
+    backend: Option<Box<dyn ClipboardBackend>>,
+    config: Config,
+) -> crate::Result<Clipboard> {
+    let backend = match backend {
+        Some(backend) => backend,
+        None => Box::new(
+            ClipboardRsContext::new().map_err(|err| Error::BackendUnavailable(err.to_string()))?,
+        ),
+    };
+    Ok(Clipboard::with_config(backend, config))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AvailableTypes {
     pub text: bool,
     pub html: bool,
@@ -25,94 +48,185 @@ pub struct AvailableTypes {
     pub files: bool,
 }
 
+impl AvailableTypes {
+    pub(crate) fn read(clipboard: &dyn ClipboardBackend) -> Self {
+        Self {
+            text: clipboard.has(ContentFormat::Text),
+            html: clipboard.has(ContentFormat::Html),
+            rtf: clipboard.has(ContentFormat::Rtf),
+            image: clipboard.has(ContentFormat::Image),
+            files: clipboard.has(ContentFormat::Files),
+        }
+    }
+
+    /// Whether any known format is on the clipboard.
+    pub fn any(&self) -> bool {
+        self.text || self.html || self.rtf || self.image || self.files
+    }
+}
+
 /// Access to the clipboard APIs.
 pub struct Clipboard {
-    pub clipboard: Arc<Mutex<ClipboardRsContext>>,
-    pub watcher_shutdown: Arc<Mutex<Option<WatcherShutdown>>>,
+    pub clipboard: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+    pub watcher_shutdown: Arc<Mutex<Option<WatchHandle>>>,
+    pub config: Config,
+    monitor: Arc<MonitorState>,
+    /// Options of the running monitor.
+    monitor_options: Mutex<MonitorOptions>,
+    clock: Arc<dyn Clock>,
+    transfers: Arc<Transfers>,
 }
 impl Clipboard {
-    pub fn has(&self, format: ContentFormat) -> Result<bool, String> {
-        Ok(self
-            .clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .has(format))
-    }
-
-    pub fn available_types(&self) -> Result<AvailableTypes, String> {
-        Ok(AvailableTypes {
-            text: self.has(ContentFormat::Text)?,
-            html: self.has(ContentFormat::Html)?,
-            rtf: self.has(ContentFormat::Rtf)?,
-            image: self.has(ContentFormat::Image)?,
-            files: self.has(ContentFormat::Files)?,
+    pub fn new(backend: Box<dyn ClipboardBackend>) -> Self {
+        Self::with_config(backend, Config::default())
+    }
+
+    pub fn with_config(backend: Box<dyn ClipboardBackend>, config: Config) -> Self {
+        Self {
+            clipboard: Arc::new(Mutex::new(backend)),
+            watcher_shutdown: Arc::default(),
+            monitor: Arc::new(MonitorState {
+                history: Mutex::new(History::new(&config.history)),
+                ..Default::default()
+            }),
+            monitor_options: Mutex::default(),
+            clock: Arc::new(SystemClock),
+            transfers: Arc::default(),
+            config,
+        }
+    }
+
+    /// Use `clock` to time the monitor's debounce and throttle windows.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        if let Ok(mut history) = self.monitor.history.lock() {
+            history.set_clock(self.clock.clone());
+        }
+        self
+    }
+
+    pub fn has(&self, format: ContentFormat) -> Result<bool> {
+        Ok(self.clipboard.lock()?.has(format))
+    }
+
+    /// Run `read` against the backend. If it fails because `format` is not on the clipboard,
+    /// report [`Error::EmptyClipboard`] or [`Error::FormatUnavailable`] instead of the raw
+    /// backend error.
+    fn read<T>(
+        &self,
+        format: ContentFormat,
+        read: impl FnOnce(&dyn ClipboardBackend) -> clipboard_rs::Result<T>,
+    ) -> Result<T> {
+        let clipboard = self.clipboard.lock()?;
+        read(clipboard.as_ref()).map_err(|err| match Error::from(err) {
+            Error::Backend(_) if !clipboard.has(format.clone()) => {
+                let formats = [
+                    ContentFormat::Text,
+                    ContentFormat::Html,
+                    ContentFormat::Rtf,
+                    ContentFormat::Image,
+                    ContentFormat::Files,
+                ];
+                if formats.into_iter().any(|format| clipboard.has(format)) {
+                    Error::FormatUnavailable(format_name(&format))
+                } else {
+                    Error::EmptyClipboard
+                }
+            }
+            err => err,
         })
     }
 
-    pub fn has_text(&self) -> Result<bool, String> {
+    pub fn available_types(&self) -> Result<AvailableTypes> {
+        Ok(AvailableTypes::read(self.clipboard.lock()?.as_ref()))
+    }
+
+    /// Read every format on the clipboard at once, so they all come from the same copy. Formats
+    /// that fail to read or exceed the size limits are left out of the content.
+    pub fn read_all(&self, options: &SnapshotOptions) -> Result<ClipboardContents> {
+        let clipboard = self.clipboard.lock()?;
+        let available = AvailableTypes::read(clipboard.as_ref());
+        let content = ClipboardSnapshot::read_with_options(
+            clipboard.as_ref(),
+            &available,
+            &self.config,
+            options,
+        );
+        Ok(ClipboardContents { available, content })
+    }
+
+    pub fn has_text(&self) -> Result<bool> {
         self.has(ContentFormat::Text)
     }
 
-    pub fn has_rtf(&self) -> Result<bool, String> {
+    pub fn has_rtf(&self) -> Result<bool> {
         self.has(ContentFormat::Rtf)
     }
 
-    pub fn has_image(&self) -> Result<bool, String> {
+    pub fn has_image(&self) -> Result<bool> {
         self.has(ContentFormat::Image)
     }
 
-    pub fn has_html(&self) -> Result<bool, String> {
+    pub fn has_html(&self) -> Result<bool> {
         self.has(ContentFormat::Html)
     }
 
-    pub fn has_files(&self) -> Result<bool, String> {
+    pub fn has_files(&self) -> Result<bool> {
         self.has(ContentFormat::Files)
     }
 
+    /// Whether `format`, a raw format name like `application/x-my-app+json`, is on the clipboard.
+    pub fn has_custom(&self, format: String) -> Result<bool> {
+        self.has(ContentFormat::Other(format))
+    }
+
+    /// Raw names of the formats on the clipboard, as reported by the platform, e.g.
+    /// `text/plain` on Linux or `public.utf8-plain-text` on macOS.
+    pub fn list_formats(&self) -> Result<Vec<String>> {
+        Ok(self.clipboard.lock()?.available_formats()?)
+    }
+
     // Read from Clipboard APIs
 
     /// read text from clipboard
-    pub fn read_text(&self) -> Result<String, String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .get_text()
-            .map_err(|err| err.to_string())
+    pub fn read_text(&self) -> Result<String> {
+        let text = self.read(ContentFormat::Text, |clipboard| clipboard.get_text())?;
+        check_size(text.len(), self.config.max_text_size)?;
+        Ok(text)
     }
 
-    pub fn read_html(&self) -> Result<String, String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .get_html()
-            .map_err(|err| err.to_string())
+    pub fn read_html(&self) -> Result<String> {
+        let html = self.read(ContentFormat::Html, |clipboard| clipboard.get_html())?;
+        check_size(html.len(), self.config.max_text_size)?;
+        Ok(html)
     }
 
-    pub fn read_rtf(&self) -> Result<String, String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .get_rich_text()
-            .map_err(|err| err.to_string())
+    pub fn read_rtf(&self) -> Result<String> {
+        let rtf = self.read(ContentFormat::Rtf, |clipboard| clipboard.get_rich_text())?;
+        check_size(rtf.len(), self.config.max_text_size)?;
+        Ok(rtf)
+    }
+
+    /// Raw content of the custom `format`.
+    pub fn read_custom(&self, format: String) -> Result<Vec<u8>> {
+        let bytes = self.read(ContentFormat::Other(format.clone()), |clipboard| {
+            clipboard.get_buffer(&format)
+        })?;
+        check_size(bytes.len(), self.config.max_image_size)?;
+        Ok(bytes)
     }
 
     /// read files from clipboard and return a `Vec<String>`
     /// Will return a vector of strings, in uri format: `file:///path/to/file`. File path is absolute path.
     /// On Windows, the path will be in the format `C:\\path\\to\\file`. This method is the same as read_files on windows
-    pub fn read_files_uris(&self) -> Result<Vec<String>, String> {
-        let files = self
-            .clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .get_files()
-            .map_err(|err| err.to_string())?;
-        Ok(files)
+    pub fn read_files_uris(&self) -> Result<Vec<String>> {
+        self.read(ContentFormat::Files, |clipboard| clipboard.get_files())
     }
 
     /// read files from clipboard and return a `Vec<String>`
     /// Will return a vector of strings, in absolute path format: `/path/to/file`.
     /// On Windows, the path will be in the format `C:\\path\\to\\file`. This method is the same as read_files_uris on windows
-    pub fn read_files(&self) -> Result<Vec<String>, String> {
+    pub fn read_files(&self) -> Result<Vec<String>> {
         let files = self.read_files_uris()?;
         // iterate through the files and remove the `file://` prefix if there is any. Only remove the prefix if it's in the beginning
         let files_str = files
@@ -130,16 +244,16 @@ impl Clipboard {
 
     /// Write files uris to clipboard. The files should be in uri format: `file:///path/to/file` on Mac and Linux. File path is absolute path.
     /// On Windows, the path should be in the format `C:\\path\\to\\file`.
-    pub fn write_files_uris(&self, files: Vec<String>) -> Result<(), String> {
+    pub fn write_files_uris(&self, files: Vec<String>) -> Result<()> {
         // iterate through files, check if it starts with files://, if not throw error (only linux and mac)
         #[cfg(any(target_os = "linux", target_os = "macos"))]
         {
             for file in &files {
                 if !file.starts_with("file://") {
-                    return Err(format!(
-                        "Invalid file uri: {}. File uri should start with file://",
-                        file
-                    ));
+                    return Err(Error::InvalidFileUri {
+                        uri: file.clone(),
+                        reason: "File uri should start with file://",
+                    });
                 }
             }
         }
@@ -148,162 +262,716 @@ impl Clipboard {
         {
             for file in &files {
                 if file.starts_with("file://") {
-                    return Err(format!(
-                        "Invalid file uri: {}. File uri on Windows should not start with file://",
-                        file
-                    ));
+                    return Err(Error::InvalidFileUri {
+                        uri: file.clone(),
+                        reason: "File uri on Windows should not start with file://",
+                    });
                 }
             }
         }
 
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set_files(files)
-            .map_err(|err| err.to_string())
+        Ok(self.clipboard.lock()?.set_files(files)?)
+    }
+
+    /// Write absolute file paths to clipboard, as uris on Mac and Linux, see [`Clipboard::write_files_uris`].
+    pub fn write_files(&self, files_paths: Vec<String>) -> Result<()> {
+        self.write_files_uris(files_uris(files_paths)?)
     }
 
     /// read image from clipboard and return a base64 string
-    pub fn read_image_base64(&self) -> Result<String, String> {
-        let image_bytes = self.read_image_binary()?;
+    pub fn read_image_base64(&self) -> Result<String> {
+        self.read_image_base64_with_options(ImageReadOptions::default())
+    }
+
+    pub fn read_image_base64_with_options(&self, options: ImageReadOptions) -> Result<String> {
+        let image_bytes = self.read_image_binary_with_options(options)?;
         let base64_str = general_purpose::STANDARD.encode(image_bytes);
         Ok(base64_str)
     }
 
-    /// read image from clipboard and return a `Vec<u8>`
-    pub fn read_image_binary(&self) -> Result<Vec<u8>, String> {
-        let image = self
-            .clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .get_image()
-            .map_err(|err| err.to_string())?;
-        let bytes = image
-            .to_png()
-            .map_err(|err| err.to_string())?
-            .get_bytes()
-            .to_vec();
-        // let bytes = util::image_data_to_bytes(&image);
+    /// read image from clipboard and return a `Vec<u8>`, encoded as configured in `Config::image_encoding`
+    pub fn read_image_binary(&self) -> Result<Vec<u8>> {
+        self.read_image_binary_with_options(ImageReadOptions::default())
+    }
+
+    /// read image from clipboard and return a `Vec<u8>`, encoded as set in `options`, falling
+    /// back to `Config::image_encoding` and `Config::jpeg_quality`
+    pub fn read_image_binary_with_options(&self, options: ImageReadOptions) -> Result<Vec<u8>> {
+        let image = self.read(ContentFormat::Image, |clipboard| clipboard.get_image())?;
+        let bytes = encode_image(
+            &image,
+            options.encoding.unwrap_or(self.config.image_encoding),
+            options.quality.unwrap_or(self.config.jpeg_quality),
+        )?;
+        check_size(bytes.len(), self.config.max_image_size)?;
         Ok(bytes)
     }
 
+    /// Dimensions and pixel layout of the image on the clipboard, and an estimate of its size
+    /// once encoded as set in `options`. Nothing is encoded.
+    pub fn image_info(&self, options: ImageReadOptions) -> Result<ImageInfo> {
+        let image = self.read(ContentFormat::Image, |clipboard| clipboard.get_image())?;
+        let (width, height) = image.get_size();
+        let color = image_color(&image)?;
+        let encoding = options.encoding.unwrap_or(self.config.image_encoding);
+        let quality = options.quality.unwrap_or(self.config.jpeg_quality);
+        Ok(ImageInfo {
+            width,
+            height,
+            color_type: color_type_name(color).to_string(),
+            has_alpha: color.has_alpha(),
+            bits_per_pixel: color.bits_per_pixel(),
+            encoding,
+            estimated_size: estimate_encoded_size(width, height, encoding, quality),
+        })
+    }
+
+    /// read image from clipboard, scaled down to fit in `max_width` x `max_height` with its
+    /// aspect ratio preserved, and encoded as set in `options`. Smaller images are not scaled up.
+    pub fn read_image_thumbnail(
+        &self,
+        max_width: u32,
+        max_height: u32,
+        options: ImageReadOptions,
+        filter: ThumbnailFilter,
+    ) -> Result<ImageThumbnail> {
+        let image = self.read(ContentFormat::Image, |clipboard| clipboard.get_image())?;
+        let (original_width, original_height) = image.get_size();
+        let (max_width, max_height) = (max_width.max(1), max_height.max(1));
+        let image = if original_width > max_width || original_height > max_height {
+            let filter = match filter {
+                ThumbnailFilter::Nearest => FilterType::Nearest,
+                ThumbnailFilter::Triangle => FilterType::Triangle,
+                ThumbnailFilter::CatmullRom => FilterType::CatmullRom,
+                ThumbnailFilter::Gaussian => FilterType::Gaussian,
+                ThumbnailFilter::Lanczos3 => FilterType::Lanczos3,
+            };
+            let resized = image
+                .get_dynamic_image()
+                .map_err(|err| Error::ImageDecode(err.to_string()))?
+                .resize(max_width, max_height, filter);
+            RustImageData::from_dynamic_image(resized)
+        } else {
+            image
+        };
+        let (width, height) = image.get_size();
+        let bytes = encode_image(
+            &image,
+            options.encoding.unwrap_or(self.config.image_encoding),
+            options.quality.unwrap_or(self.config.jpeg_quality),
+        )?;
+        check_size(bytes.len(), self.config.max_image_size)?;
+        Ok(ImageThumbnail {
+            bytes,
+            width,
+            height,
+            original_width,
+            original_height,
+        })
+    }
+
+    /// read image from clipboard as raw RGBA8 pixels, skipping any encoding
+    pub fn read_image_rgba(&self) -> Result<RawImage> {
+        let image = self.read(ContentFormat::Image, |clipboard| clipboard.get_image())?;
+        let (width, height) = image.get_size();
+        check_size(rgba_len(width, height)?, self.config.max_image_size)?;
+        let rgba = image
+            .to_rgba8()
+            .map_err(|err| Error::ImageDecode(err.to_string()))?
+            .into_raw();
+        Ok(RawImage {
+            width,
+            height,
+            rgba,
+        })
+    }
+
     // Write to Clipboard APIs
-    pub fn write_text(&self, text: String) -> Result<(), String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set_text(text)
-            .map_err(|err| err.to_string())
+    pub fn write_text(&self, text: String) -> Result<()> {
+        check_size(text.len(), self.config.max_text_size)?;
+        Ok(self.clipboard.lock()?.set_text(text)?)
     }
 
-    pub fn write_html(&self, html: String) -> Result<(), String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set_html(html)
-            .map_err(|err| err.to_string())
+    pub fn write_html(&self, html: String) -> Result<()> {
+        check_size(html.len(), self.config.max_text_size)?;
+        Ok(self.clipboard.lock()?.set_html(html)?)
     }
 
-    pub fn write_html_and_text(&self, html: String, text: String) -> Result<(), String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set(vec![
-                ClipboardContent::Text(text),
-                ClipboardContent::Html(html),
-            ])
-            .map_err(|err| err.to_string())
+    pub fn write_html_and_text(&self, html: String, text: String) -> Result<()> {
+        check_size(html.len(), self.config.max_text_size)?;
+        check_size(text.len(), self.config.max_text_size)?;
+        Ok(self.clipboard.lock()?.set(vec![
+            ClipboardContent::Text(text),
+            ClipboardContent::Html(html),
+        ])?)
     }
 
-    pub fn write_rtf(&self, rtf: String) -> Result<(), String> {
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set_rich_text(rtf)
-            .map_err(|err| err.to_string())
+    pub fn write_rtf(&self, rtf: String) -> Result<()> {
+        check_size(rtf.len(), self.config.max_text_size)?;
+        Ok(self.clipboard.lock()?.set_rich_text(rtf)?)
+    }
+
+    /// Replace the clipboard content with `bytes` in the custom `format`. Use
+    /// [`Clipboard::write`] to offer it next to other formats.
+    pub fn write_custom(&self, format: String, bytes: Vec<u8>) -> Result<()> {
+        check_size(bytes.len(), self.config.max_image_size)?;
+        Ok(self.clipboard.lock()?.set_buffer(&format, bytes)?)
     }
 
     /// write base64 png image to clipboard
-    pub fn write_image_base64(&self, base64_image: String) -> Result<(), String> {
-        let decoded = general_purpose::STANDARD
-            .decode(base64_image)
-            .map_err(|err| err.to_string())?;
+    pub fn write_image_base64(&self, base64_image: String) -> Result<()> {
+        let decoded = general_purpose::STANDARD.decode(base64_image)?;
         self.write_image_binary(decoded)
-            .map_err(|err| err.to_string())?;
-        Ok(())
     }
 
-    pub fn write_image_binary(&self, bytes: Vec<u8>) -> Result<(), String> {
-        let img = RustImageData::from_bytes(bytes.as_bytes()).map_err(|err| err.to_string())?;
-        self.clipboard
-            .lock()
-            .map_err(|err| err.to_string())?
-            .set_image(img)
-            .unwrap();
-        Ok(())
+    /// write an encoded image to clipboard, in any format the `image` crate decodes (PNG, JPEG,
+    /// WebP, BMP, GIF, ...). The format is detected from the content.
+    pub fn write_image_binary(&self, bytes: Vec<u8>) -> Result<()> {
+        check_size(bytes.len(), self.config.max_image_size)?;
+        let img = decode_image(&bytes)?;
+        Ok(self.clipboard.lock()?.set_image(img)?)
     }
 
-    pub fn clear(&self) -> Result<(), String> {
-        self.clipboard.lock().unwrap().clear().unwrap();
-        Ok(())
+    /// Write every item in one operation, so other applications see all representations at
+    /// once. Nothing is written if any item is too large or an image cannot be decoded.
+    pub fn write(&self, items: Vec<ClipboardItem>) -> Result<()> {
+        let contents = items
+            .into_iter()
+            .map(|item| {
+                Ok(match item {
+                    ClipboardItem::Text { text } => {
+                        check_size(text.len(), self.config.max_text_size)?;
+                        ClipboardContent::Text(text)
+                    }
+                    ClipboardItem::Html { html } => {
+                        check_size(html.len(), self.config.max_text_size)?;
+                        ClipboardContent::Html(html)
+                    }
+                    ClipboardItem::Rtf { rtf } => {
+                        check_size(rtf.len(), self.config.max_text_size)?;
+                        ClipboardContent::Rtf(rtf)
+                    }
+                    ClipboardItem::Image { bytes } => {
+                        check_size(bytes.len(), self.config.max_image_size)?;
+                        ClipboardContent::Image(decode_image(&bytes)?)
+                    }
+                    ClipboardItem::Files { paths } => ClipboardContent::Files(files_uris(paths)?),
+                    ClipboardItem::Custom { format, bytes } => {
+                        check_size(bytes.len(), self.config.max_image_size)?;
+                        ClipboardContent::Other(format, bytes)
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.clipboard.lock()?.set(contents)?)
     }
 
-    pub fn start_monitor<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<(), String> {
-        let _ = app_handle.emit("plugin:clipboard://clipboard-monitor/status", true);
-        let clipboard = ClipboardMonitor::new(app_handle);
-        let mut watcher: ClipboardWatcherContext<ClipboardMonitor<R>> =
-            ClipboardWatcherContext::new().unwrap();
-        let watcher_shutdown = watcher.add_handler(clipboard).get_shutdown_channel();
-        let mut watcher_shutdown_state = self.watcher_shutdown.lock().unwrap();
-        if (*watcher_shutdown_state).is_some() {
-            return Ok(());
+    /// write raw RGBA8 pixels to clipboard, skipping any decoding. `image.rgba` must hold
+    /// exactly `width * height * 4` bytes.
+    pub fn write_image_rgba(&self, image: RawImage) -> Result<()> {
+        check_size(image.rgba.len(), self.config.max_image_size)?;
+        let expected = rgba_len(image.width, image.height)?;
+        if image.rgba.len() != expected {
+            return Err(Error::ImageDecode(format!(
+                "expected {} bytes of RGBA pixels for a {}x{} image, got {}",
+                expected,
+                image.width,
+                image.height,
+                image.rgba.len()
+            )));
         }
-        *watcher_shutdown_state = Some(watcher_shutdown);
-        std::thread::spawn(move || {
-            watcher.start_watch();
-        });
+        let pixels = image::RgbaImage::from_raw(image.width, image.height, image.rgba)
+            .ok_or_else(|| Error::ImageDecode("invalid RGBA image".to_string()))?;
+        let img = RustImageData::from_dynamic_image(pixels.into());
+        Ok(self.clipboard.lock()?.set_image(img)?)
+    }
+
+    /// Read `format` and send it through `channel` in chunks of at most
+    /// `ChunkedReadOptions::chunk_size` bytes, from a background thread. The channel receives a
+    /// `Started` event with the total size, then every chunk as raw bytes followed by a
+    /// `Progress` event, and finally `Finished` or `Cancelled`, see [`crate::TransferEvent`].
+    /// Returns the transfer id, see [`Clipboard::cancel_transfer`].
+    pub fn read_chunked(
+        &self,
+        format: TransferFormat,
+        options: ChunkedReadOptions,
+        channel: Channel,
+    ) -> Result<u64> {
+        let payload = match format {
+            TransferFormat::Text => self.read_text()?.into_bytes(),
+            TransferFormat::Html => self.read_html()?.into_bytes(),
+            TransferFormat::Rtf => self.read_rtf()?.into_bytes(),
+            TransferFormat::Image => self.read_image_binary_with_options(options.image)?,
+        };
+        self.transfers.start_read(
+            payload,
+            options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE),
+            channel,
+        )
+    }
+
+    /// Start writing `size` bytes of `format` in chunks, returning the transfer id. Text formats
+    /// are sent as UTF-8, images in any format [`Clipboard::write_image_binary`] accepts. A write
+    /// that receives no chunk for [`crate::WRITE_TIMEOUT`] is dropped.
+    pub fn write_chunked_begin(&self, format: TransferFormat, size: u64) -> Result<u64> {
+        let limit = match format {
+            TransferFormat::Image => self.config.max_image_size,
+            _ => self.config.max_text_size,
+        };
+        check_size(usize::try_from(size).unwrap_or(usize::MAX), limit)?;
+        self.transfers.begin_write(format, size, self.clock.now())
+    }
+
+    pub fn write_chunked_append(&self, id: u64, chunk: &[u8]) -> Result<TransferProgress> {
+        self.transfers.append(id, chunk, self.clock.now())
+    }
+
+    /// Write the payload of the transfer `id` to the clipboard once every byte was received.
+    pub fn write_chunked_finish(&self, id: u64) -> Result<()> {
+        let (format, bytes) = self.transfers.finish_write(id)?;
+        let text = |bytes: Vec<u8>| {
+            String::from_utf8(bytes).map_err(|err| Error::InvalidRequest(err.to_string()))
+        };
+        match format {
+            TransferFormat::Text => self.write_text(text(bytes)?),
+            TransferFormat::Html => self.write_html(text(bytes)?),
+            TransferFormat::Rtf => self.write_rtf(text(bytes)?),
+            TransferFormat::Image => self.write_image_binary(bytes),
+        }
+    }
+
+    /// Cancel the chunked read or write `id`.
+    pub fn cancel_transfer(&self, id: u64) -> Result<()> {
+        self.transfers.cancel(id)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        Ok(self.clipboard.lock()?.clear()?)
+    }
+
+    pub fn start_monitor<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<()> {
+        self.start_monitor_with_options(app_handle, MonitorOptions::default())
+    }
+
+    /// Start the clipboard monitor, emitting a `ClipboardChangeEvent` on every clipboard change.
+    /// Does nothing if the monitor is already running with the same options, and restarts it if
+    /// they differ.
+    pub fn start_monitor_with_options<R: Runtime>(
+        &self,
+        app_handle: AppHandle<R>,
+        options: MonitorOptions,
+    ) -> Result<()> {
+        let mut watcher_shutdown_state = self.watcher_shutdown.lock()?;
+        let mut monitor_options = self.monitor_options.lock()?;
+        if watcher_shutdown_state.is_some() && *monitor_options != options {
+            if let Some(watcher_shutdown) = watcher_shutdown_state.take() {
+                watcher_shutdown.stop();
+            }
+        }
+        if (*watcher_shutdown_state).is_none() {
+            *monitor_options = options.clone();
+            let monitor = ClipboardMonitor::new(
+                app_handle.clone(),
+                self.clipboard.clone(),
+                self.config.clone(),
+                options,
+                self.monitor.clone(),
+                self.clock.clone(),
+            );
+            let watcher_shutdown = self.clipboard.lock()?.watch(Box::new(monitor)).map_err(
+                |err| match Error::from(err) {
+                    Error::Backend(message) => Error::BackendUnavailable(message),
+                    err => err,
+                },
+            )?;
+            *watcher_shutdown_state = Some(watcher_shutdown);
+        }
+        let _ = app_handle.emit(&self.config.event_name("clipboard-monitor/status"), true);
         Ok(())
     }
 
-    pub fn stop_monitor<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<(), String> {
-        let _ = app_handle.emit("plugin:clipboard://clipboard-monitor/status", false);
-        let mut watcher_shutdown_state = self.watcher_shutdown.lock().unwrap();
+    pub fn stop_monitor<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<()> {
+        let _ = app_handle.emit(&self.config.event_name("clipboard-monitor/status"), false);
+        let mut watcher_shutdown_state = self.watcher_shutdown.lock()?;
         if let Some(watcher_shutdown) = (*watcher_shutdown_state).take() {
             watcher_shutdown.stop();
         }
         *watcher_shutdown_state = None;
+        *self.monitor.fingerprint.lock()? = None;
+        Ok(())
+    }
+
+    pub fn is_monitor_running(&self) -> Result<bool> {
+        Ok((*self.watcher_shutdown.lock()?).is_some())
+    }
+
+    /// Hash of the clipboard content, as a hex string. Equal fingerprints mean equal content.
+    /// While the monitor runs this is the fingerprint it computed on the last change, otherwise
+    /// it is computed from the current content.
+    pub fn fingerprint(&self) -> Result<String> {
+        if let Some(fingerprint) = *self.monitor.fingerprint.lock()? {
+            return Ok(format_fingerprint(fingerprint));
+        }
+        let clipboard = self.clipboard.lock()?;
+        let available = AvailableTypes::read(clipboard.as_ref());
+        Ok(format_fingerprint(fingerprint(
+            clipboard.as_ref(),
+            &available,
+        )))
+    }
+
+    /// Ask the monitor to emit `events`. Subscriptions are counted, each event is emitted once
+    /// per clipboard change however many times it was subscribed to.
+    pub fn subscribe_format_events(&self, events: &[FormatEvent]) -> Result<()> {
+        let mut subscriptions = self.monitor.format_subscriptions.lock()?;
+        for event in events {
+            *subscriptions.entry(*event).or_default() += 1;
+        }
+        Ok(())
+    }
+
+    /// Undo one [`Clipboard::subscribe_format_events`] call for each of `events`.
+    pub fn unsubscribe_format_events(&self, events: &[FormatEvent]) -> Result<()> {
+        let mut subscriptions = self.monitor.format_subscriptions.lock()?;
+        for event in events {
+            if let Some(count) = subscriptions.get_mut(event) {
+                *count = count.saturating_sub(1);
+            }
+        }
+        Ok(())
+    }
+
+    /// Call `callback` on every change seen by the monitor, from the monitor's thread. The
+    /// monitor is shared with the webview, so nothing is delivered until it is started with
+    /// [`Clipboard::start_monitor`]. Dropping the returned handle unsubscribes.
+    pub fn subscribe(
+        &self,
+        callback: impl Fn(&ClipboardChangeEvent) + Send + Sync + 'static,
+    ) -> Result<Subscription> {
+        let id = self.monitor.subscribers.lock()?.add(Arc::new(callback));
+        Ok(Subscription::new(id, &self.monitor))
+    }
+
+    /// Entries recorded by the monitor when `HistoryConfig::enabled` is set that match
+    /// `filter`, newest first. Payloads larger than `HistoryConfig::blob_threshold` of a
+    /// persisted history are left out, use [`Clipboard::history_get`] to read them.
+    pub fn history_list(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        self.monitor.history.lock()?.list(filter)
+    }
+
+    pub fn history_get(&self, id: u64) -> Result<HistoryEntry> {
+        self.monitor
+            .history
+            .lock()?
+            .get(id)?
+            .ok_or(Error::HistoryEntryNotFound(id))
+    }
+
+    /// Search the text, HTML, RTF and file paths of the history. Every word of `query` must
+    /// match a word of the entry or the start of one. Returns `limit` hits starting at `offset`,
+    /// best match first.
+    pub fn history_search(
+        &self,
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<HistorySearchResults> {
+        self.monitor.history.lock()?.search(query, offset, limit)
+    }
+
+    pub fn history_delete<R: Runtime>(&self, app_handle: AppHandle<R>, id: u64) -> Result<()> {
+        let removed = self
+            .monitor
+            .history
+            .lock()?
+            .delete(id)?
+            .ok_or(Error::HistoryEntryNotFound(id))?;
+        self.emit_history_changed(
+            &app_handle,
+            HistoryChangedEvent {
+                removed: vec![removed],
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Write every format captured with an entry back to the clipboard at once. The monitor
+    /// does not record the restored content as a new entry.
+    pub fn history_restore(&self, id: u64) -> Result<()> {
+        let content = self.history_get(id)?.content;
+        let mut contents = Vec::new();
+        if let Some(text) = content.text {
+            contents.push(ClipboardContent::Text(text));
+        }
+        if let Some(html) = content.html {
+            contents.push(ClipboardContent::Html(html));
+        }
+        if let Some(rtf) = content.rtf {
+            contents.push(ClipboardContent::Rtf(rtf));
+        }
+        if let Some(image) = content.image {
+            let bytes = general_purpose::STANDARD.decode(image)?;
+            contents.push(ClipboardContent::Image(decode_image(&bytes)?));
+        }
+        if let Some(files) = content.files {
+            contents.push(ClipboardContent::Files(files));
+        }
+        let clipboard = self.clipboard.lock()?;
+        // set before the clipboard lock is released, the monitor reads the clipboard under it
+        let mut restored = self.monitor.restored.lock()?;
+        clipboard.set(contents)?;
+        let available = AvailableTypes::read(clipboard.as_ref());
+        *restored = Some(fingerprint(clipboard.as_ref(), &available));
         Ok(())
     }
 
-    pub fn is_monitor_running(&self) -> bool {
-        (*self.watcher_shutdown.lock().unwrap()).is_some()
+    /// Pin an entry, so it is never evicted and survives [`Clipboard::history_clear`].
+    pub fn history_pin<R: Runtime>(&self, app_handle: AppHandle<R>, id: u64) -> Result<()> {
+        self.history_set_pinned(app_handle, id, true)
+    }
+
+    pub fn history_unpin<R: Runtime>(&self, app_handle: AppHandle<R>, id: u64) -> Result<()> {
+        self.history_set_pinned(app_handle, id, false)
+    }
+
+    fn history_set_pinned<R: Runtime>(
+        &self,
+        app_handle: AppHandle<R>,
+        id: u64,
+        pinned: bool,
+    ) -> Result<()> {
+        if !self.monitor.history.lock()?.set_pinned(id, pinned)? {
+            return Err(Error::HistoryEntryNotFound(id));
+        }
+        self.emit_history_changed(
+            &app_handle,
+            HistoryChangedEvent {
+                updated: vec![id],
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Replace the tags of an entry. Tags are trimmed, empty and duplicate tags are dropped.
+    pub fn history_set_tags<R: Runtime>(
+        &self,
+        app_handle: AppHandle<R>,
+        id: u64,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        if !self.monitor.history.lock()?.set_tags(id, tags)? {
+            return Err(Error::HistoryEntryNotFound(id));
+        }
+        self.emit_history_changed(
+            &app_handle,
+            HistoryChangedEvent {
+                updated: vec![id],
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Enforce `HistoryConfig::retention` now, emitting the evicted entries as
+    /// `history-changed`.
+    pub fn history_sweep<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<()> {
+        let removed = self.monitor.history.lock()?.sweep()?;
+        self.emit_history_changed(
+            &app_handle,
+            HistoryChangedEvent {
+                removed,
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    /// Enforce `HistoryConfig::retention` in the background, every
+    /// `RetentionPolicy::sweep_interval_ms`, until the clipboard is dropped. Does nothing if
+    /// the sweeper is already running.
+    pub fn start_history_sweeper<R: Runtime>(&self, app_handle: AppHandle<R>) {
+        if self.monitor.sweeper_started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        spawn_sweeper(
+            Arc::downgrade(&self.monitor),
+            app_handle,
+            self.config.event_name("history-changed"),
+            self.config.history.retention.sweep_interval(),
+        );
+    }
+
+    /// Remove every entry that is not pinned.
+    pub fn history_clear<R: Runtime>(&self, app_handle: AppHandle<R>) -> Result<()> {
+        let removed = self.monitor.history.lock()?.clear()?;
+        self.emit_history_changed(
+            &app_handle,
+            HistoryChangedEvent {
+                removed,
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    fn emit_history_changed<R: Runtime>(
+        &self,
+        app_handle: &AppHandle<R>,
+        changed: HistoryChangedEvent,
+    ) {
+        if !changed.is_empty() {
+            let _ = app_handle.emit(&self.config.event_name("history-changed"), changed);
+        }
+    }
+
+    /// Like [`Clipboard::subscribe`], but delivers the changes as a `Stream`.
+    pub fn watch(&self) -> Result<ClipboardWatch> {
+        let queue = Arc::new(Mutex::new(WatchQueue::default()));
+        let subscription = self.subscribe({
+            let queue = queue.clone();
+            move |event| WatchQueue::push(&queue, event)
+        })?;
+        Ok(ClipboardWatch::new(queue, subscription))
     }
 }
 
-pub struct ClipboardMonitor<R>
-where
-    R: Runtime,
-{
-    app_handle: tauri::AppHandle<R>,
+/// Convert absolute file paths to the uris expected by [`Clipboard::write_files_uris`].
+fn files_uris(files_paths: Vec<String>) -> Result<Vec<String>> {
+    for file in &files_paths {
+        if file.starts_with("file://") {
+            return Err(Error::InvalidFileUri {
+                uri: file.clone(),
+                reason: "File uri should not start with file://",
+            });
+        }
+    }
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    let files_uris = files_paths
+        .into_iter()
+        .map(|file| format!("file://{}", file))
+        .collect();
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let files_uris = files_paths;
+    Ok(files_uris)
 }
 
-impl<R> ClipboardMonitor<R>
-where
-    R: Runtime,
-{
-    pub fn new(app_handle: tauri::AppHandle<R>) -> Self {
-        Self { app_handle }
+/// Number of bytes of a `width` x `height` RGBA8 image, failing if it does not fit in a `usize`.
+fn rgba_len(width: u32, height: u32) -> Result<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .ok_or_else(|| Error::InvalidRequest(format!("a {}x{} image is too large", width, height)))
+}
+
+/// Fail with [`Error::PayloadTooLarge`] if `size` exceeds `limit`.
+fn check_size(size: usize, limit: Option<usize>) -> Result<()> {
+    match limit {
+        Some(limit) if size > limit => Err(Error::PayloadTooLarge { size, limit }),
+        _ => Ok(()),
     }
 }
 
-impl<R> ClipboardHandler for ClipboardMonitor<R>
-where
-    R: Runtime,
-{
-    fn on_clipboard_change(&mut self) {
-        let _ = self.app_handle.emit(
-            "plugin:clipboard://clipboard-monitor/update",
-            "clipboard update",
-        );
+pub(crate) fn encode_image(
+    image: &RustImageData,
+    encoding: ImageEncoding,
+    jpeg_quality: u8,
+) -> Result<Vec<u8>> {
+    let image = image
+        .get_dynamic_image()
+        .map_err(|err| Error::ImageEncode(err.to_string()))?;
+    let mut bytes = Vec::new();
+    match encoding {
+        ImageEncoding::Png => image
+            .to_rgba8()
+            .write_with_encoder(PngEncoder::new(&mut bytes)),
+        // JPEG has no alpha channel
+        ImageEncoding::Jpeg => image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(
+                &mut bytes,
+                jpeg_quality.clamp(1, 100),
+            )),
+        ImageEncoding::Webp => image
+            .to_rgba8()
+            .write_with_encoder(WebPEncoder::new_lossless(&mut bytes)),
+        ImageEncoding::Bmp => image
+            .to_rgba8()
+            .write_with_encoder(BmpEncoder::new(&mut bytes)),
+    }
+    .map_err(|err| Error::ImageEncode(err.to_string()))?;
+    Ok(bytes)
+}
+
+fn color_type_name(color: ColorType) -> &'static str {
+    match color {
+        ColorType::L8 => "l8",
+        ColorType::La8 => "la8",
+        ColorType::Rgb8 => "rgb8",
+        ColorType::Rgba8 => "rgba8",
+        ColorType::L16 => "l16",
+        ColorType::La16 => "la16",
+        ColorType::Rgb16 => "rgb16",
+        ColorType::Rgba16 => "rgba16",
+        ColorType::Rgb32F => "rgb32F",
+        ColorType::Rgba32F => "rgba32F",
+        _ => "unknown",
+    }
+}
+
+/// Pixel layout of `image`, without copying its pixels: `get_dynamic_image` clones the whole
+/// buffer, so a single pixel is sampled instead, which keeps the color type.
+fn image_color(image: &RustImageData) -> Result<ColorType> {
+    let (width, height) = image.get_size();
+    let sample = if width == 0 || height == 0 {
+        image.get_dynamic_image()
+    } else {
+        image
+            .resize(1, 1, FilterType::Nearest)
+            .and_then(|pixel| pixel.get_dynamic_image())
+    };
+    Ok(sample
+        .map_err(|err| Error::ImageDecode(err.to_string()))?
+        .color())
+}
+
+/// Size of the image once encoded by [`encode_image`]. Images are encoded as 8-bit RGBA, RGB
+/// for JPEG. Compressed sizes assume a typical screenshot: PNG and WebP keep about a third and a
+/// quarter of the pixel data, JPEG from 0.5 to 4 bits per pixel depending on the quality.
+fn estimate_encoded_size(
+    width: u32,
+    height: u32,
+    encoding: ImageEncoding,
+    jpeg_quality: u8,
+) -> u64 {
+    let pixels = u64::from(width) * u64::from(height);
+    match encoding {
+        // BITMAPFILEHEADER and BITMAPV4HEADER, then 4 bytes per pixel
+        ImageEncoding::Bmp => 14 + 108 + pixels * 4,
+        ImageEncoding::Png => pixels * 4 / 3,
+        ImageEncoding::Webp => pixels,
+        ImageEncoding::Jpeg => {
+            let quality = u64::from(jpeg_quality.clamp(1, 100));
+            pixels * (50 + 350 * quality / 100) / 800
+        }
+    }
+}
+
+/// Decode an image in any format supported by the `image` crate, guessed from its content.
+fn decode_image(bytes: &[u8]) -> Result<RustImageData> {
+    let image =
+        image::load_from_memory(bytes).map_err(|err| Error::ImageDecode(err.to_string()))?;
+    Ok(RustImageData::from_dynamic_image(image))
+}
+
+fn format_name(format: &ContentFormat) -> String {
+    match format {
+        ContentFormat::Text => "text".to_string(),
+        ContentFormat::Rtf => "rtf".to_string(),
+        ContentFormat::Html => "html".to_string(),
+        ContentFormat::Image => "image".to_string(),
+        ContentFormat::Files => "files".to_string(),
+        ContentFormat::Other(format) => format.clone(),
     }
 }
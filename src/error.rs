@@ -9,6 +9,75 @@ pub enum Error {
     #[cfg(mobile)]
     #[error(transparent)]
     PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
+    #[error("Clipboard is empty")]
+    EmptyClipboard,
+    #[error("Clipboard has no {0} content")]
+    FormatUnavailable(String),
+    #[error("Invalid file uri: {uri}. {reason}")]
+    InvalidFileUri { uri: String, reason: &'static str },
+    #[error("Failed to decode image: {0}")]
+    ImageDecode(String),
+    #[error("Failed to encode image: {0}")]
+    ImageEncode(String),
+    #[error(transparent)]
+    Base64Decode(#[from] base64::DecodeError),
+    #[error("Clipboard backend unavailable: {0}")]
+    BackendUnavailable(String),
+    #[error("Payload of {size} bytes exceeds the limit of {limit} bytes")]
+    PayloadTooLarge { size: usize, limit: usize },
+    #[error("No history entry with id {0}")]
+    HistoryEntryNotFound(u64),
+    #[error("No transfer with id {0}")]
+    TransferNotFound(u64),
+    /// A command received a body it cannot parse.
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Clipboard lock poisoned")]
+    LockPoisoned,
+    /// Any other error reported by the clipboard backend.
+    #[error("{0}")]
+    Backend(String),
+}
+
+impl Error {
+    /// Stable identifier of the variant, sent to the frontend as `kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            #[cfg(mobile)]
+            Error::PluginInvoke(_) => "pluginInvoke",
+            Error::EmptyClipboard => "emptyClipboard",
+            Error::FormatUnavailable(_) => "formatUnavailable",
+            Error::InvalidFileUri { .. } => "invalidFileUri",
+            Error::ImageDecode(_) => "imageDecode",
+            Error::ImageEncode(_) => "imageEncode",
+            Error::Base64Decode(_) => "base64Decode",
+            Error::BackendUnavailable(_) => "backendUnavailable",
+            Error::PayloadTooLarge { .. } => "payloadTooLarge",
+            Error::HistoryEntryNotFound(_) => "historyEntryNotFound",
+            Error::TransferNotFound(_) => "transferNotFound",
+            Error::InvalidRequest(_) => "invalidRequest",
+            Error::LockPoisoned => "lockPoisoned",
+            Error::Backend(_) => "backend",
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for Error {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        Error::LockPoisoned
+    }
+}
+
+/// Backends report errors as `clipboard_rs::Result`. A boxed [`Error`] is passed through as is,
+/// anything else becomes [`Error::Backend`].
+impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
+    fn from(err: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        match err.downcast::<Error>() {
+            Ok(err) => *err,
+            Err(err) => Error::Backend(err.to_string()),
+        }
+    }
 }
 
 impl Serialize for Error {
@@ -16,6 +85,11 @@ impl Serialize for Error {
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Error", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
     }
 }
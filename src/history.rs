@@ -0,0 +1,475 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Weak},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::history_store::{HistoryStore, StoredEntry};
+use crate::monitor::MonitorState;
+use crate::search::{self, HistorySearchHit, HistorySearchResults, SearchIndex};
+use crate::{
+    AvailableTypes, ClipboardSnapshot, Clock, HistoryConfig, HistoryFormat, Result,
+    RetentionPolicy, SystemClock,
+};
+
+/// A clipboard change recorded by the monitor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: u64,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Fingerprint of the content, see `Clipboard::fingerprint`.
+    pub hash: String,
+    pub available: AvailableTypes,
+    pub content: ClipboardSnapshot,
+    /// Size in bytes of the captured payloads, images counted decoded from base64.
+    #[serde(default)]
+    pub size: u64,
+    /// Pinned entries are never evicted and survive `history_clear`.
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl HistoryFormat {
+    fn is_available(self, available: &AvailableTypes) -> bool {
+        match self {
+            HistoryFormat::Text => available.text,
+            HistoryFormat::Html => available.html,
+            HistoryFormat::Rtf => available.rtf,
+            HistoryFormat::Image => available.image,
+            HistoryFormat::Files => available.files,
+        }
+    }
+}
+
+/// Criteria of `history_list`. Unset criteria match every entry.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryFilter {
+    /// Only entries with this tag.
+    pub tag: Option<String>,
+    /// Only pinned or only unpinned entries.
+    pub pinned: Option<bool>,
+    /// Only entries with this format.
+    pub format: Option<HistoryFormat>,
+}
+
+impl HistoryFilter {
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        self.tag
+            .as_ref()
+            .map_or(true, |tag| entry.tags.contains(tag))
+            && self.pinned.map_or(true, |pinned| entry.pinned == pinned)
+            && self
+                .format
+                .map_or(true, |format| format.is_available(&entry.available))
+    }
+}
+
+/// Payload of the `history-changed` event.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryChangedEvent {
+    pub added: Vec<u64>,
+    pub removed: Vec<u64>,
+    /// Entries whose pin state or tags changed.
+    pub updated: Vec<u64>,
+}
+
+impl HistoryChangedEvent {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// The last `max_entries` clipboard changes, oldest first, plus the pinned entries. When
+/// persisted, the entries are read from disk on first use rather than when the plugin starts.
+pub(crate) struct History {
+    entries: VecDeque<StoredEntry>,
+    next_id: u64,
+    max_entries: usize,
+    store: Option<HistoryStore>,
+    loaded: bool,
+    index: SearchIndex,
+    retention: RetentionPolicy,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(&HistoryConfig::default())
+    }
+}
+
+impl History {
+    pub fn new(config: &HistoryConfig) -> Self {
+        let store = match (config.persist, &config.directory) {
+            (true, Some(directory)) => Some(HistoryStore::new(directory.clone(), config)),
+            _ => None,
+        };
+        Self {
+            entries: VecDeque::new(),
+            next_id: 1,
+            max_entries: config.max_entries,
+            store,
+            loaded: false,
+            index: SearchIndex::default(),
+            retention: config.retention.clone(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    fn now_millis(&self) -> u64 {
+        millis(self.clock.system_time())
+    }
+
+    fn load(&mut self) -> Result<()> {
+        if self.loaded {
+            return Ok(());
+        }
+        if let Some(store) = &mut self.store {
+            let (entries, next_id) = store.load()?;
+            self.entries = entries.into();
+            for stored in &self.entries {
+                self.index.insert(stored.entry.id, stored.search_terms());
+            }
+            self.next_id = next_id;
+        }
+        self.loaded = true;
+        Ok(())
+    }
+
+    /// Record a new entry. Copying content that is already in the history moves it to the top
+    /// instead of adding a duplicate.
+    pub fn push(
+        &mut self,
+        hash: String,
+        available: AvailableTypes,
+        content: ClipboardSnapshot,
+    ) -> Result<HistoryChangedEvent> {
+        self.load()?;
+        let mut changed = HistoryChangedEvent::default();
+        let mut pinned = false;
+        let mut tags = Vec::new();
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|stored| stored.entry.hash == hash)
+        {
+            let previous = &self.entries[index].entry;
+            pinned = previous.pinned;
+            tags = previous.tags.clone();
+            changed.removed.extend(self.remove(index)?);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let entry = HistoryEntry {
+            id,
+            timestamp: self.now_millis(),
+            hash,
+            available,
+            size: content_size(&content),
+            content,
+            pinned,
+            tags,
+        };
+        let stored = match &mut self.store {
+            Some(store) => store.add(entry)?,
+            None => StoredEntry::new(entry),
+        };
+        self.index.insert(id, stored.search_terms());
+        self.entries.push_back(stored);
+        changed.added.push(id);
+        // pinned entries do not count toward `max_entries`
+        while self
+            .entries
+            .iter()
+            .filter(|stored| !stored.entry.pinned)
+            .count()
+            > self.max_entries
+        {
+            match self.entries.iter().position(|stored| !stored.entry.pinned) {
+                Some(index) => changed.removed.extend(self.remove(index)?),
+                None => break,
+            }
+        }
+        changed.removed.extend(self.evict_expired()?);
+        self.compact()?;
+        Ok(changed)
+    }
+
+    /// Enforce the retention policy, returning the ids of the evicted entries.
+    pub fn sweep(&mut self) -> Result<Vec<u64>> {
+        self.load()?;
+        let evicted = self.evict_expired()?;
+        if !evicted.is_empty() {
+            self.compact()?;
+        }
+        Ok(evicted)
+    }
+
+    fn evict_expired(&mut self) -> Result<Vec<u64>> {
+        let retention = &self.retention;
+        if !retention.has_limits() {
+            return Ok(Vec::new());
+        }
+        let now = self.now_millis();
+        let expired = |timestamp: u64, max_age_secs: Option<u64>| {
+            max_age_secs
+                .is_some_and(|max_age| now.saturating_sub(timestamp) > max_age.saturating_mul(1000))
+        };
+        let mut total_bytes = 0;
+        let mut kept = HashMap::<HistoryFormat, (usize, u64)>::new();
+        let mut evicted = Vec::new();
+        // newest first, so the oldest entries are evicted once a limit is reached
+        for stored in self.entries.iter().rev() {
+            let entry = &stored.entry;
+            if entry.pinned {
+                continue;
+            }
+            let mut evict = expired(entry.timestamp, retention.max_age_secs)
+                || retention
+                    .max_total_bytes
+                    .is_some_and(|max| total_bytes + entry.size > max);
+            for (format, limits) in &retention.formats {
+                if !format.is_available(&entry.available) {
+                    continue;
+                }
+                let (count, bytes) = kept.get(format).copied().unwrap_or_default();
+                evict = evict
+                    || expired(entry.timestamp, limits.max_age_secs)
+                    || limits.max_entries.is_some_and(|max| count >= max)
+                    || limits
+                        .max_total_bytes
+                        .is_some_and(|max| bytes + entry.size > max);
+            }
+            if evict {
+                evicted.push(entry.id);
+                continue;
+            }
+            total_bytes += entry.size;
+            for format in retention.formats.keys() {
+                if format.is_available(&entry.available) {
+                    let (count, bytes) = kept.entry(*format).or_default();
+                    *count += 1;
+                    *bytes += entry.size;
+                }
+            }
+        }
+        for id in &evicted {
+            if let Some(index) = self
+                .entries
+                .iter()
+                .position(|stored| stored.entry.id == *id)
+            {
+                self.remove(index)?;
+            }
+        }
+        Ok(evicted)
+    }
+
+    fn remove(&mut self, index: usize) -> Result<Option<u64>> {
+        let Some(stored) = self.entries.remove(index) else {
+            return Ok(None);
+        };
+        self.index.remove(stored.entry.id);
+        if let Some(store) = &mut self.store {
+            store.delete(&stored)?;
+        }
+        Ok(Some(stored.entry.id))
+    }
+
+    fn compact(&mut self) -> Result<()> {
+        match &mut self.store {
+            Some(store) => store.maybe_compact(self.entries.iter(), self.next_id),
+            None => Ok(()),
+        }
+    }
+
+    /// Entries matching `filter`, newest first. Payloads stored as blobs are left out, see
+    /// [`History::get`].
+    pub fn list(&mut self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        self.load()?;
+        Ok(self
+            .entries
+            .iter()
+            .rev()
+            .filter(|stored| filter.matches(&stored.entry))
+            .map(|stored| stored.entry.clone())
+            .collect())
+    }
+
+    /// Returns whether the entry exists.
+    pub fn set_pinned(&mut self, id: u64, pinned: bool) -> Result<bool> {
+        self.load()?;
+        let Some(stored) = self.entries.iter_mut().find(|stored| stored.entry.id == id) else {
+            return Ok(false);
+        };
+        if let Some(store) = &mut self.store {
+            store.pin(id, pinned)?;
+        }
+        stored.entry.pinned = pinned;
+        Ok(true)
+    }
+
+    /// Replace the tags of an entry. Returns whether the entry exists.
+    pub fn set_tags(&mut self, id: u64, tags: Vec<String>) -> Result<bool> {
+        self.load()?;
+        let Some(stored) = self.entries.iter_mut().find(|stored| stored.entry.id == id) else {
+            return Ok(false);
+        };
+        let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let tag = tag.trim();
+            if !tag.is_empty() && !normalized.iter().any(|existing| existing == tag) {
+                normalized.push(tag.to_string());
+            }
+        }
+        if let Some(store) = &mut self.store {
+            store.set_tags(id, &normalized)?;
+        }
+        stored.entry.tags = normalized;
+        Ok(true)
+    }
+
+    /// The entry `id` with all of its payloads.
+    pub fn get(&mut self, id: u64) -> Result<Option<HistoryEntry>> {
+        self.load()?;
+        let Some(stored) = self.entries.iter().find(|stored| stored.entry.id == id) else {
+            return Ok(None);
+        };
+        Ok(Some(match &self.store {
+            Some(store) => store.load_blobs(stored),
+            None => stored.entry.clone(),
+        }))
+    }
+
+    /// Entries matching every word of `query`, best match first, then newest first.
+    pub fn search(
+        &mut self,
+        query: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<HistorySearchResults> {
+        self.load()?;
+        let terms = search::query_terms(query);
+        if terms.is_empty() {
+            return Ok(HistorySearchResults {
+                total: 0,
+                hits: Vec::new(),
+            });
+        }
+        let mut scores = self.index.search(&terms);
+        scores.sort_by(|(a_id, a_score), (b_id, b_score)| {
+            b_score.total_cmp(a_score).then(b_id.cmp(a_id))
+        });
+        let hits = scores
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|(id, score)| {
+                let stored = self.entries.iter().find(|stored| stored.entry.id == *id)?;
+                let content = match &self.store {
+                    Some(store) => store.load_search_blobs(stored).content,
+                    None => stored.entry.content.clone(),
+                };
+                Some(HistorySearchHit {
+                    entry: stored.entry.clone(),
+                    score: *score,
+                    highlights: search::highlights(&content, &terms),
+                })
+            })
+            .collect();
+        Ok(HistorySearchResults {
+            total: scores.len(),
+            hits,
+        })
+    }
+
+    pub fn delete(&mut self, id: u64) -> Result<Option<u64>> {
+        self.load()?;
+        match self.entries.iter().position(|stored| stored.entry.id == id) {
+            Some(index) => {
+                let removed = self.remove(index)?;
+                self.compact()?;
+                Ok(removed)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove every entry that is not pinned, returning their ids.
+    pub fn clear(&mut self) -> Result<Vec<u64>> {
+        self.load()?;
+        let (pinned, entries): (Vec<StoredEntry>, Vec<StoredEntry>) = self
+            .entries
+            .drain(..)
+            .partition(|stored| stored.entry.pinned);
+        self.entries = pinned.into();
+        for stored in &entries {
+            self.index.remove(stored.entry.id);
+        }
+        if let Some(store) = &mut self.store {
+            store.clear(&entries)?;
+        }
+        self.compact()?;
+        Ok(entries.into_iter().map(|stored| stored.entry.id).collect())
+    }
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn content_size(content: &ClipboardSnapshot) -> u64 {
+    let text = |text: &Option<String>| text.as_ref().map_or(0, String::len);
+    let size = text(&content.text)
+        + text(&content.html)
+        + text(&content.rtf)
+        + text(&content.image) / 4 * 3
+        + content
+            .files
+            .as_ref()
+            .map_or(0, |files| files.iter().map(String::len).sum());
+    size as u64
+}
+
+/// Enforce the retention policy every `RetentionPolicy::sweep_interval`, starting now, until
+/// the `Clipboard` owning `state` is dropped. Evictions are emitted as `history-changed`.
+pub(crate) fn spawn_sweeper<R: Runtime>(
+    state: Weak<MonitorState>,
+    app_handle: AppHandle<R>,
+    event: String,
+    interval: Duration,
+) {
+    std::thread::spawn(move || {
+        while let Some(state) = state.upgrade() {
+            let evicted = match state.history.lock() {
+                Ok(mut history) => history.sweep().unwrap_or_default(),
+                Err(_) => return,
+            };
+            drop(state);
+            if !evicted.is_empty() {
+                let _ = app_handle.emit(
+                    &event,
+                    HistoryChangedEvent {
+                        removed: evicted,
+                        ..Default::default()
+                    },
+                );
+            }
+            std::thread::sleep(interval);
+        }
+    });
+}
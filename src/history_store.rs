@@ -0,0 +1,457 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::history::HistoryEntry;
+use crate::search::{self, SearchField};
+use crate::{HistoryConfig, Result};
+
+const LOG_FILE: &str = "history.log";
+const BLOB_DIR: &str = "blobs";
+
+/// A line of the history log.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub(crate) enum Record {
+    Add {
+        entry: Box<HistoryEntry>,
+        /// Payloads of `entry` stored as blobs instead of inline.
+        #[serde(default)]
+        blobs: Vec<BlobField>,
+        /// Search terms of the blobs, so they need not be read to build the search index.
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        terms: HashMap<String, u32>,
+    },
+    Delete {
+        id: u64,
+    },
+    /// Removes every entry that is not pinned.
+    Clear,
+    Pin {
+        id: u64,
+        pinned: bool,
+    },
+    Tags {
+        id: u64,
+        tags: Vec<String>,
+    },
+    /// Ids below `next_id` were handed out. Written at the start of a compacted log, which
+    /// no longer holds the records of deleted entries.
+    NextId {
+        next_id: u64,
+    },
+}
+
+/// Payload of an entry that can be stored next to the log as a blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum BlobField {
+    Text,
+    Html,
+    Rtf,
+    Image,
+    Files,
+}
+
+impl BlobField {
+    const ALL: [BlobField; 5] = [
+        BlobField::Text,
+        BlobField::Html,
+        BlobField::Rtf,
+        BlobField::Image,
+        BlobField::Files,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            BlobField::Text => "text",
+            BlobField::Html => "html",
+            BlobField::Rtf => "rtf",
+            BlobField::Image => "image",
+            BlobField::Files => "files",
+        }
+    }
+
+    /// Take the payload out of `entry`, encoded as the blob content.
+    fn take(self, entry: &mut HistoryEntry) -> Option<Vec<u8>> {
+        let content = &mut entry.content;
+        match self {
+            BlobField::Text => content.text.take().map(String::into_bytes),
+            BlobField::Html => content.html.take().map(String::into_bytes),
+            BlobField::Rtf => content.rtf.take().map(String::into_bytes),
+            // stored decoded, a third smaller than the base64 string
+            BlobField::Image => content
+                .image
+                .take()
+                .and_then(|image| general_purpose::STANDARD.decode(image).ok()),
+            BlobField::Files => content
+                .files
+                .take()
+                .and_then(|files| serde_json::to_vec(&files).ok()),
+        }
+    }
+
+    /// Put a blob read back from disk into `entry`.
+    fn restore(self, entry: &mut HistoryEntry, bytes: Vec<u8>) {
+        let content = &mut entry.content;
+        match self {
+            BlobField::Text => content.text = String::from_utf8(bytes).ok(),
+            BlobField::Html => content.html = String::from_utf8(bytes).ok(),
+            BlobField::Rtf => content.rtf = String::from_utf8(bytes).ok(),
+            BlobField::Image => content.image = Some(general_purpose::STANDARD.encode(bytes)),
+            BlobField::Files => content.files = serde_json::from_slice(&bytes).ok(),
+        }
+    }
+
+    fn search_field(self) -> Option<SearchField> {
+        match self {
+            BlobField::Text => Some(SearchField::Text),
+            BlobField::Html => Some(SearchField::Html),
+            BlobField::Rtf => Some(SearchField::Rtf),
+            BlobField::Image => None,
+            BlobField::Files => Some(SearchField::Files),
+        }
+    }
+
+    fn size(self, entry: &HistoryEntry) -> usize {
+        let content = &entry.content;
+        match self {
+            BlobField::Text => content.text.as_ref().map_or(0, String::len),
+            BlobField::Html => content.html.as_ref().map_or(0, String::len),
+            BlobField::Rtf => content.rtf.as_ref().map_or(0, String::len),
+            BlobField::Image => content.image.as_ref().map_or(0, String::len),
+            BlobField::Files => content
+                .files
+                .as_ref()
+                .map_or(0, |files| files.iter().map(String::len).sum()),
+        }
+    }
+}
+
+/// A history entry together with the payloads that were moved to blobs.
+#[derive(Clone)]
+pub(crate) struct StoredEntry {
+    pub entry: HistoryEntry,
+    pub blobs: Vec<BlobField>,
+    /// Search terms of the blobs.
+    pub terms: HashMap<String, u32>,
+}
+
+impl StoredEntry {
+    pub fn new(entry: HistoryEntry) -> Self {
+        Self {
+            entry,
+            blobs: Vec::new(),
+            terms: HashMap::new(),
+        }
+    }
+
+    /// Search terms of the whole entry, including its blobs.
+    pub fn search_terms(&self) -> HashMap<String, u32> {
+        let mut terms = search::content_terms(&self.entry.content);
+        for (term, count) in &self.terms {
+            *terms.entry(term.clone()).or_default() += count;
+        }
+        terms
+    }
+
+    fn record(&self) -> Record {
+        Record::Add {
+            entry: Box::new(self.entry.clone()),
+            blobs: self.blobs.clone(),
+            terms: self.terms.clone(),
+        }
+    }
+}
+
+/// Append-only log of history changes, with large payloads stored as blob files next to it.
+///
+/// Every change is appended as one JSON line and synced before returning. Blobs are written
+/// before the record referencing them, so a crash can at worst leave an unreferenced blob or a
+/// torn last line, both of which are cleaned up on the next load.
+pub(crate) struct HistoryStore {
+    dir: PathBuf,
+    log: Option<File>,
+    log_len: u64,
+    /// Size of the log after the last compaction.
+    compacted_len: u64,
+    blob_threshold: usize,
+    compact_threshold: u64,
+}
+
+impl HistoryStore {
+    pub fn new(dir: PathBuf, config: &HistoryConfig) -> Self {
+        Self {
+            dir,
+            log: None,
+            log_len: 0,
+            compacted_len: 0,
+            blob_threshold: config.blob_threshold,
+            compact_threshold: config.compact_threshold,
+        }
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.dir.join(LOG_FILE)
+    }
+
+    fn blob_path(&self, id: u64, field: BlobField) -> PathBuf {
+        self.dir
+            .join(BLOB_DIR)
+            .join(format!("{}-{}", id, field.name()))
+    }
+
+    /// Replay the log, oldest entry first. Records that cannot be parsed are skipped. When they
+    /// end the log, like a record torn by a crash, they are cut off so new records are appended
+    /// after the last good one.
+    /// Blobs that no entry references, left by a crash between a blob and its record, are removed.
+    ///
+    /// Returns the live entries and the next free id, above every id ever recorded so ids held
+    /// by clients are never reused for another entry.
+    pub fn load(&mut self) -> Result<(Vec<StoredEntry>, u64)> {
+        fs::create_dir_all(self.dir.join(BLOB_DIR))?;
+        let log = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(self.log_path())?;
+        let mut entries = Vec::<StoredEntry>::new();
+        let mut next_id = 1;
+        let mut valid_len = 0;
+        let mut offset = 0;
+        let mut reader = BufReader::new(&log);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            offset += read as u64;
+            let Ok(record) = serde_json::from_slice::<Record>(&line) else {
+                continue;
+            };
+            if !line.ends_with(b"\n") {
+                break;
+            }
+            valid_len = offset;
+            match record {
+                Record::Add {
+                    entry,
+                    blobs,
+                    terms,
+                } => {
+                    next_id = next_id.max(entry.id + 1);
+                    entries.push(StoredEntry {
+                        entry: *entry,
+                        blobs,
+                        terms,
+                    })
+                }
+                Record::Delete { id } => entries.retain(|stored| stored.entry.id != id),
+                Record::Clear => entries.retain(|stored| stored.entry.pinned),
+                Record::Pin { id, pinned } => {
+                    if let Some(stored) = entries.iter_mut().find(|stored| stored.entry.id == id) {
+                        stored.entry.pinned = pinned;
+                    }
+                }
+                Record::Tags { id, tags } => {
+                    if let Some(stored) = entries.iter_mut().find(|stored| stored.entry.id == id) {
+                        stored.entry.tags = tags;
+                    }
+                }
+                Record::NextId { next_id: id } => next_id = next_id.max(id),
+            }
+        }
+        if valid_len < log.metadata()?.len() {
+            log.set_len(valid_len)?;
+            log.sync_data()?;
+        }
+        self.log = Some(log);
+        self.log_len = valid_len;
+        self.compacted_len = valid_len;
+        self.remove_unreferenced_blobs(entries.iter())?;
+        Ok((entries, next_id))
+    }
+
+    fn append(&mut self, record: &Record) -> Result<()> {
+        let mut line = serde_json::to_vec(record).map_err(io::Error::from)?;
+        line.push(b'\n');
+        let log = match &mut self.log {
+            Some(log) => log,
+            None => {
+                return Err(io::Error::new(io::ErrorKind::Other, "history log is not open").into())
+            }
+        };
+        log.write_all(&line)?;
+        log.sync_data()?;
+        self.log_len += line.len() as u64;
+        Ok(())
+    }
+
+    /// Append `entry`, moving payloads larger than the blob threshold out of it into blobs.
+    pub fn add(&mut self, mut entry: HistoryEntry) -> Result<StoredEntry> {
+        let mut blobs = Vec::new();
+        let mut terms = HashMap::new();
+        for field in BlobField::ALL {
+            if field.size(&entry) <= self.blob_threshold {
+                continue;
+            }
+            if let Some(text) = field
+                .search_field()
+                .and_then(|search_field| search_field.text(&entry.content))
+            {
+                search::text_terms(&text, &mut terms);
+            }
+            if let Some(bytes) = field.take(&mut entry) {
+                write_atomic(&self.blob_path(entry.id, field), &bytes)?;
+                blobs.push(field);
+            }
+        }
+        let stored = StoredEntry {
+            entry,
+            blobs,
+            terms,
+        };
+        self.append(&stored.record())?;
+        Ok(stored)
+    }
+
+    pub fn delete(&mut self, stored: &StoredEntry) -> Result<()> {
+        self.append(&Record::Delete {
+            id: stored.entry.id,
+        })?;
+        self.remove_blobs(stored);
+        Ok(())
+    }
+
+    pub fn pin(&mut self, id: u64, pinned: bool) -> Result<()> {
+        self.append(&Record::Pin { id, pinned })
+    }
+
+    pub fn set_tags(&mut self, id: u64, tags: &[String]) -> Result<()> {
+        self.append(&Record::Tags {
+            id,
+            tags: tags.to_vec(),
+        })
+    }
+
+    /// Record a [`Record::Clear`], which removed `entries`.
+    pub fn clear(&mut self, entries: &[StoredEntry]) -> Result<()> {
+        self.append(&Record::Clear)?;
+        for stored in entries {
+            self.remove_blobs(stored);
+        }
+        Ok(())
+    }
+
+    fn remove_blobs(&self, stored: &StoredEntry) {
+        for field in &stored.blobs {
+            let _ = fs::remove_file(self.blob_path(stored.entry.id, *field));
+        }
+    }
+
+    /// Copy of `stored` with its blobs read back. Missing blobs are left out.
+    pub fn load_blobs(&self, stored: &StoredEntry) -> HistoryEntry {
+        self.load_fields(stored, |_| true)
+    }
+
+    /// Like [`HistoryStore::load_blobs`], but only reads the searchable blobs.
+    pub fn load_search_blobs(&self, stored: &StoredEntry) -> HistoryEntry {
+        self.load_fields(stored, |field| field.search_field().is_some())
+    }
+
+    fn load_fields(&self, stored: &StoredEntry, load: impl Fn(BlobField) -> bool) -> HistoryEntry {
+        let mut entry = stored.entry.clone();
+        for field in stored.blobs.iter().filter(|field| load(**field)) {
+            if let Ok(bytes) = fs::read(self.blob_path(entry.id, *field)) {
+                field.restore(&mut entry, bytes);
+            }
+        }
+        entry
+    }
+
+    /// Rewrite the log with only the `live` entries once it has grown past the compaction
+    /// threshold and to twice its size after the last compaction. Blobs no longer referenced
+    /// are removed. `next_id` is kept so ids of deleted entries are not handed out again.
+    pub fn maybe_compact<'a>(
+        &mut self,
+        live: impl Iterator<Item = &'a StoredEntry> + Clone,
+        next_id: u64,
+    ) -> Result<()> {
+        if self.log_len < self.compact_threshold || self.log_len < self.compacted_len * 2 {
+            return Ok(());
+        }
+        let tmp_path = self.dir.join(format!("{}.tmp", LOG_FILE));
+        let mut tmp = File::create(&tmp_path)?;
+        let mut len = 0;
+        let records = std::iter::once(Record::NextId { next_id })
+            .chain(live.clone().map(StoredEntry::record));
+        for record in records {
+            let mut line = serde_json::to_vec(&record).map_err(io::Error::from)?;
+            line.push(b'\n');
+            tmp.write_all(&line)?;
+            len += line.len() as u64;
+        }
+        tmp.sync_all()?;
+        drop(tmp);
+        fs::rename(&tmp_path, self.log_path())?;
+        sync_dir(&self.dir)?;
+        self.log = Some(OpenOptions::new().append(true).open(self.log_path())?);
+        self.log_len = len;
+        self.compacted_len = len;
+        self.remove_unreferenced_blobs(live)
+    }
+
+    /// Remove the blobs, and leftover temporary files, that none of `live` references.
+    fn remove_unreferenced_blobs<'a>(
+        &self,
+        live: impl Iterator<Item = &'a StoredEntry>,
+    ) -> Result<()> {
+        let referenced: HashSet<PathBuf> = live
+            .flat_map(|stored| {
+                stored
+                    .blobs
+                    .iter()
+                    .map(|field| self.blob_path(stored.entry.id, *field))
+            })
+            .collect();
+        for blob in fs::read_dir(self.dir.join(BLOB_DIR))?.flatten() {
+            if !referenced.contains(&blob.path()) {
+                let _ = fs::remove_file(blob.path());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `bytes` to a temporary file and rename it over `path`, so `path` is never partial.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut tmp = File::create(&tmp_path)?;
+    tmp.write_all(bytes)?;
+    tmp.sync_all()?;
+    fs::rename(tmp_path, path)?;
+    match path.parent() {
+        Some(dir) => sync_dir(dir),
+        None => Ok(()),
+    }
+}
+
+/// Sync `dir`, so the renames in it survive a crash.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<()> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories cannot be opened as files on Windows, where renames are journaled by NTFS.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<()> {
+    Ok(())
+}
@@ -1,61 +1,221 @@
 pub use models::*;
 use tauri::{
-    plugin::{Builder, TauriPlugin},
+    plugin::{Builder as PluginBuilder, TauriPlugin},
     Manager, Runtime,
 };
 
+#[cfg(desktop)]
+mod backend;
+mod clock;
 mod commands;
+mod config;
 #[cfg(desktop)]
 mod desktop;
 mod error;
+#[cfg(desktop)]
+mod history;
+#[cfg(desktop)]
+mod history_store;
 #[cfg(mobile)]
 mod mobile;
+#[cfg(all(desktop, feature = "mock"))]
+mod mock;
 mod models;
-pub mod utils;
+#[cfg(desktop)]
+mod monitor;
+#[cfg(desktop)]
+mod search;
+#[cfg(desktop)]
+mod subscription;
+#[cfg(desktop)]
+mod transfer;
+#[cfg(desktop)]
+mod utils;
 pub use error::{Error, Result};
 
 #[cfg(desktop)]
-pub use desktop::Clipboard;
+pub use backend::{ClipboardBackend, WatchHandle};
+pub use clipboard_rs;
+pub use clock::{Clock, SystemClock};
+pub use config::{Config, FormatRetention, HistoryConfig, RetentionPolicy};
+#[cfg(desktop)]
+pub use desktop::{AvailableTypes, Clipboard};
+#[cfg(desktop)]
+pub use history::{HistoryChangedEvent, HistoryEntry, HistoryFilter};
 #[cfg(mobile)]
 pub use mobile::Clipboard;
+#[cfg(all(desktop, feature = "mock"))]
+pub use mock::{MockClipboard, MockClock};
+#[cfg(desktop)]
+pub use monitor::{
+    ClipboardChangeEvent, ClipboardContents, ClipboardSnapshot, FormatEvent, MonitorOptions,
+    SnapshotOptions,
+};
+#[cfg(desktop)]
+pub use search::{Highlight, HistorySearchHit, HistorySearchResults, SearchField};
+#[cfg(desktop)]
+pub use subscription::{ClipboardWatch, Subscription};
+#[cfg(desktop)]
+pub use transfer::{
+    ChunkedReadOptions, TransferEvent, TransferFormat, TransferProgress, DEFAULT_CHUNK_SIZE,
+    WRITE_TIMEOUT,
+};
 
 /// Initializes the plugin.
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("clipboard")
-        .invoke_handler(tauri::generate_handler![
-            commands::stop_monitor,
-            commands::start_monitor,
-            commands::is_monitor_running,
-            commands::has_text,
-            commands::has_image,
-            commands::has_html,
-            commands::has_rtf,
-            commands::has_files,
-            commands::available_types,
-            commands::read_text,
-            commands::read_files,
-            commands::read_files_uris,
-            commands::read_html,
-            commands::read_image_base64,
-            commands::read_image_binary,
-            commands::read_rtf,
-            commands::write_text,
-            commands::write_html,
-            commands::write_html_and_text,
-            commands::write_rtf,
-            commands::write_image_binary,
-            commands::write_image_base64,
-            commands::write_files_uris,
-            commands::write_files,
-            commands::clear
-        ])
-        .setup(|app, api| {
-            #[cfg(mobile)]
-            let clipboard = mobile::init(app, api)?;
-            #[cfg(desktop)]
-            let clipboard = desktop::init(api)?;
-            app.manage(clipboard);
-            Ok(())
-        })
-        .build()
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+    Builder::new().build()
+}
+
+/// Builder for the clipboard plugin.
+#[derive(Default)]
+pub struct Builder {
+    #[cfg(desktop)]
+    backend: Option<Box<dyn ClipboardBackend>>,
+    config: config::ConfigOverrides,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom clipboard backend instead of `clipboard_rs::ClipboardContext`.
+    #[cfg(desktop)]
+    pub fn backend(mut self, backend: impl ClipboardBackend + 'static) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Start the clipboard monitor when the plugin is initialized.
+    pub fn auto_start_monitor(mut self, auto_start_monitor: bool) -> Self {
+        self.config.auto_start_monitor = Some(auto_start_monitor);
+        self
+    }
+
+    /// Encoding of images returned by `read_image_binary` and `read_image_base64`.
+    pub fn image_encoding(mut self, image_encoding: ImageEncoding) -> Self {
+        self.config.image_encoding = Some(image_encoding);
+        self
+    }
+
+    /// Quality of JPEG images, from 1 to 100.
+    pub fn jpeg_quality(mut self, jpeg_quality: u8) -> Self {
+        self.config.jpeg_quality = Some(jpeg_quality);
+        self
+    }
+
+    /// Maximum size in bytes of text, html and rtf payloads read or written.
+    pub fn max_text_size(mut self, max_text_size: usize) -> Self {
+        self.config.max_text_size = Some(max_text_size);
+        self
+    }
+
+    /// Maximum size in bytes of encoded images and custom format payloads read or written.
+    pub fn max_image_size(mut self, max_image_size: usize) -> Self {
+        self.config.max_image_size = Some(max_image_size);
+        self
+    }
+
+    /// Clipboard history: whether it is recorded, how much is kept and where it is persisted.
+    pub fn history(mut self, history: HistoryConfig) -> Self {
+        self.config.history = Some(history);
+        self
+    }
+
+    /// Prefix of every event emitted by the plugin, `plugin:clipboard://` by default.
+    pub fn event_prefix(mut self, event_prefix: impl Into<String>) -> Self {
+        self.config.event_prefix = Some(event_prefix.into());
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R, Option<Config>> {
+        #[cfg(desktop)]
+        let backend = self.backend;
+        let overrides = self.config;
+        PluginBuilder::<R, Option<Config>>::new("clipboard")
+            .invoke_handler(tauri::generate_handler![
+                commands::stop_monitor,
+                commands::start_monitor,
+                commands::is_monitor_running,
+                commands::subscribe_format_events,
+                commands::unsubscribe_format_events,
+                commands::has_text,
+                commands::has_image,
+                commands::has_html,
+                commands::has_rtf,
+                commands::has_files,
+                commands::has_custom,
+                commands::list_formats,
+                commands::available_types,
+                commands::read_all,
+                commands::clipboard_fingerprint,
+                commands::read_text,
+                commands::read_files,
+                commands::read_files_uris,
+                commands::read_html,
+                commands::image_info,
+                commands::read_image_base64,
+                commands::read_image_binary,
+                commands::read_image_rgba,
+                commands::read_image_thumbnail,
+                commands::read_rtf,
+                commands::read_custom,
+                commands::read_chunked,
+                commands::cancel_transfer,
+                commands::write_text,
+                commands::write_html,
+                commands::write_html_and_text,
+                commands::write_rtf,
+                commands::write,
+                commands::write_custom,
+                commands::write_image_binary,
+                commands::write_image_base64,
+                commands::write_image_rgba,
+                commands::write_chunked_begin,
+                commands::write_chunked_append,
+                commands::write_chunked_finish,
+                commands::write_files_uris,
+                commands::write_files,
+                commands::clear,
+                commands::history_list,
+                commands::history_get,
+                commands::history_delete,
+                commands::history_clear,
+                commands::history_search,
+                commands::history_pin,
+                commands::history_unpin,
+                commands::history_set_tags,
+                commands::history_restore
+            ])
+            .setup(move |app, api| {
+                let mut config = api.config().clone().unwrap_or_default();
+                overrides.apply(&mut config);
+                if config.history.persist {
+                    let directory = config
+                        .history
+                        .directory
+                        .take()
+                        .unwrap_or_else(|| "clipboard-history".into());
+                    config.history.directory = Some(app.path().app_data_dir()?.join(directory));
+                }
+                #[cfg(mobile)]
+                let clipboard = mobile::init(app, api)?;
+                #[cfg(desktop)]
+                let clipboard = desktop::init(api, backend, config)?;
+                app.manage(clipboard);
+                #[cfg(desktop)]
+                {
+                    let clipboard = app.state::<Clipboard>();
+                    if clipboard.config.auto_start_monitor {
+                        clipboard.start_monitor(app.clone())?;
+                    }
+                    let history = &clipboard.config.history;
+                    if history.enabled && history.retention.has_limits() {
+                        clipboard.start_history_sweeper(app.clone());
+                    }
+                }
+                Ok(())
+            })
+            .build()
+    }
 }
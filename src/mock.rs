@@ -0,0 +1,312 @@
+use clipboard_rs::{
+    common::RustImage, ClipboardContent, ClipboardHandler, ContentFormat, Result, RustImageData,
+};
+use image::DynamicImage;
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::backend::{ClipboardBackend, WatchHandle};
+use crate::Clock;
+
+/// In-memory clipboard backend, for tests and environments without a display server.
+///
+/// Clones share the same content, so a test can keep one handle to simulate copies made by
+/// other applications while the plugin owns another. Like the system clipboard, every write
+/// replaces the whole content, and watchers are notified from their own thread.
+#[derive(Clone, Default)]
+pub struct MockClipboard {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Default)]
+struct MockState {
+    text: Option<String>,
+    html: Option<String>,
+    rtf: Option<String>,
+    image: Option<DynamicImage>,
+    files: Option<Vec<String>>,
+    other: HashMap<String, Vec<u8>>,
+    watchers: Vec<(usize, Sender<Notification>)>,
+    next_watcher_id: usize,
+}
+
+impl MockState {
+    fn clear(&mut self) {
+        self.text = None;
+        self.html = None;
+        self.rtf = None;
+        self.image = None;
+        self.files = None;
+        self.other.clear();
+    }
+
+    fn notify(&mut self) {
+        self.watchers
+            .retain(|(_, sender)| sender.send(Notification::Changed).is_ok());
+    }
+}
+
+/// Message sent to the thread of a watcher.
+enum Notification {
+    Changed,
+    /// Answered once every earlier change was handled.
+    Flush(Sender<()>),
+}
+
+/// Unregisters a watcher when its [`WatchHandle`] is dropped.
+struct MockWatch {
+    id: usize,
+    state: Weak<Mutex<MockState>>,
+}
+
+impl Drop for MockWatch {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            if let Ok(mut state) = state.lock() {
+                state.watchers.retain(|(id, _)| *id != self.id);
+            }
+        }
+    }
+}
+
+impl MockClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wait until every watcher has handled the changes made so far, so a test can check that a
+    /// change did not lead to an event without sleeping.
+    pub fn settle(&self) {
+        let replies: Vec<_> = match self.state.lock() {
+            Ok(state) => state
+                .watchers
+                .iter()
+                .filter_map(|(_, sender)| {
+                    let (reply, receiver) = mpsc::channel();
+                    sender.send(Notification::Flush(reply)).ok()?;
+                    Some(receiver)
+                })
+                .collect(),
+            Err(_) => return,
+        };
+        for reply in replies {
+            // fails only if the watcher was dropped meanwhile
+            let _ = reply.recv();
+        }
+    }
+
+    fn read<T>(&self, name: &str, f: impl FnOnce(&MockState) -> Option<T>) -> Result<T> {
+        let state = self.state.lock().map_err(|err| err.to_string())?;
+        f(&state).ok_or_else(|| format!("no {} in clipboard", name).into())
+    }
+}
+
+impl ClipboardBackend for MockClipboard {
+    /// Known formats are reported by their MIME type, like on Linux.
+    fn available_formats(&self) -> Result<Vec<String>> {
+        let state = self.state.lock().map_err(|err| err.to_string())?;
+        let known = [
+            ("text/plain", state.text.is_some()),
+            ("text/html", state.html.is_some()),
+            ("text/rtf", state.rtf.is_some()),
+            ("image/png", state.image.is_some()),
+            ("text/uri-list", state.files.is_some()),
+        ];
+        let mut formats: Vec<String> = known
+            .into_iter()
+            .filter(|(_, available)| *available)
+            .map(|(format, _)| format.to_string())
+            .collect();
+        let mut other: Vec<String> = state.other.keys().cloned().collect();
+        other.sort();
+        formats.extend(other);
+        Ok(formats)
+    }
+
+    fn has(&self, format: ContentFormat) -> bool {
+        let Ok(state) = self.state.lock() else {
+            return false;
+        };
+        match format {
+            ContentFormat::Text => state.text.is_some(),
+            ContentFormat::Html => state.html.is_some(),
+            ContentFormat::Rtf => state.rtf.is_some(),
+            ContentFormat::Image => state.image.is_some(),
+            ContentFormat::Files => state.files.is_some(),
+            ContentFormat::Other(format) => state.other.contains_key(&format),
+        }
+    }
+
+    fn get_buffer(&self, format: &str) -> Result<Vec<u8>> {
+        self.read(format, |state| state.other.get(format).cloned())
+    }
+
+    fn get_text(&self) -> Result<String> {
+        self.read("text", |state| state.text.clone())
+    }
+
+    fn get_html(&self) -> Result<String> {
+        self.read("html", |state| state.html.clone())
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        self.read("rtf", |state| state.rtf.clone())
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        self.read("image", |state| {
+            state.image.clone().map(RustImageData::from_dynamic_image)
+        })
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        self.read("files", |state| state.files.clone())
+    }
+
+    fn set_text(&self, text: String) -> Result<()> {
+        self.set(vec![ClipboardContent::Text(text)])
+    }
+
+    fn set_html(&self, html: String) -> Result<()> {
+        self.set(vec![ClipboardContent::Html(html)])
+    }
+
+    fn set_rich_text(&self, rtf: String) -> Result<()> {
+        self.set(vec![ClipboardContent::Rtf(rtf)])
+    }
+
+    fn set_image(&self, image: RustImageData) -> Result<()> {
+        self.set(vec![ClipboardContent::Image(image)])
+    }
+
+    fn set_files(&self, files: Vec<String>) -> Result<()> {
+        self.set(vec![ClipboardContent::Files(files)])
+    }
+
+    fn set_buffer(&self, format: &str, buffer: Vec<u8>) -> Result<()> {
+        self.set(vec![ClipboardContent::Other(format.to_string(), buffer)])
+    }
+
+    fn set(&self, contents: Vec<ClipboardContent>) -> Result<()> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        state.clear();
+        for content in contents {
+            match content {
+                ClipboardContent::Text(text) => state.text = Some(text),
+                ClipboardContent::Html(html) => state.html = Some(html),
+                ClipboardContent::Rtf(rtf) => state.rtf = Some(rtf),
+                ClipboardContent::Image(image) => state.image = image.get_dynamic_image().ok(),
+                ClipboardContent::Files(files) => state.files = Some(files),
+                ClipboardContent::Other(format, bytes) => {
+                    state.other.insert(format, bytes);
+                }
+            }
+        }
+        state.notify();
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        state.clear();
+        state.notify();
+        Ok(())
+    }
+
+    fn watch(&self, mut handler: Box<dyn ClipboardHandler + Send>) -> Result<WatchHandle> {
+        let (sender, receiver) = mpsc::channel();
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let id = state.next_watcher_id;
+        state.next_watcher_id += 1;
+        state.watchers.push((id, sender));
+        std::thread::spawn(move || {
+            for notification in receiver {
+                match notification {
+                    Notification::Changed => handler.on_clipboard_change(),
+                    Notification::Flush(reply) => {
+                        let _ = reply.send(());
+                    }
+                }
+            }
+        });
+        Ok(WatchHandle::new(MockWatch {
+            id,
+            state: Arc::downgrade(&self.state),
+        }))
+    }
+}
+
+type AdvanceListener = Box<dyn Fn() -> bool + Send + Sync>;
+
+/// Simulated clock that only moves when [`MockClock::advance`] is called. Clones share the
+/// same time.
+#[derive(Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<(Instant, SystemTime)>>,
+    listeners: Arc<Mutex<Vec<AdvanceListener>>>,
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self {
+            now: Arc::new(Mutex::new((Instant::now(), SystemTime::now()))),
+            listeners: Arc::default(),
+        }
+    }
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the time forward. A running monitor emits the changes that became due before this
+    /// returns.
+    pub fn advance(&self, duration: Duration) {
+        if let Ok(mut now) = self.now.lock() {
+            now.0 += duration;
+            now.1 += duration;
+        }
+        // called without holding the lock, so listeners may register new listeners
+        let listeners = match self.listeners.lock() {
+            Ok(mut listeners) => std::mem::take(&mut *listeners),
+            Err(_) => return,
+        };
+        let kept: Vec<AdvanceListener> = listeners
+            .into_iter()
+            .filter(|listener| listener())
+            .collect();
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.extend(kept);
+        }
+    }
+
+    fn get(&self) -> (Instant, SystemTime) {
+        self.now
+            .lock()
+            .map(|now| *now)
+            .unwrap_or_else(|err| *err.into_inner())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.get().0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        self.get().1
+    }
+
+    fn on_advance(&self, listener: AdvanceListener) {
+        if let Ok(mut listeners) = self.listeners.lock() {
+            listeners.push(listener);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{Error, Result};
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PingRequest {
@@ -11,3 +13,168 @@ pub struct PingRequest {
 pub struct PingResponse {
     pub value: Option<String>,
 }
+
+/// Encoding of images read from the clipboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageEncoding {
+    #[default]
+    Png,
+    /// Lossy, quality set by `Config::jpeg_quality` or `ImageReadOptions::quality`. The alpha
+    /// channel is dropped.
+    Jpeg,
+    /// Lossless WebP.
+    Webp,
+    Bmp,
+}
+
+/// Options of an image read, overriding `Config::image_encoding` and `Config::jpeg_quality`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImageReadOptions {
+    pub encoding: Option<ImageEncoding>,
+    /// JPEG quality, from 1 to 100.
+    pub quality: Option<u8>,
+}
+
+/// Resampling filter of `Clipboard::read_image_thumbnail`, from fastest to sharpest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThumbnailFilter {
+    Nearest,
+    #[default]
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+/// Result of `Clipboard::read_image_thumbnail`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageThumbnail {
+    /// Encoded thumbnail.
+    pub bytes: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Dimensions of the image on the clipboard.
+    pub original_width: u32,
+    pub original_height: u32,
+}
+
+impl ImageThumbnail {
+    /// Binary IPC form: `width`, `height`, `original_width` and `original_height` as
+    /// little-endian `u32`s, then the encoded thumbnail.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + self.bytes.len());
+        for value in [
+            self.width,
+            self.height,
+            self.original_width,
+            self.original_height,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+}
+
+/// Result of `Clipboard::image_info`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// Layout of the pixels, like `rgba8` or `rgb16`.
+    pub color_type: String,
+    pub has_alpha: bool,
+    pub bits_per_pixel: u16,
+    pub encoding: ImageEncoding,
+    /// Rough size in bytes of the image read with `encoding`, exact for BMP.
+    pub estimated_size: u64,
+}
+
+/// Uncompressed image, 4 bytes per pixel in RGBA order, row by row from the top left.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl RawImage {
+    /// Binary IPC form: `width` and `height` as little-endian `u32`s, then the pixels.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.rgba.len());
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.rgba);
+        bytes
+    }
+
+    /// Parse the form written by [`RawImage::to_bytes`].
+    pub fn from_bytes(mut bytes: Vec<u8>) -> Result<Self> {
+        if bytes.len() < 8 {
+            return Err(Error::ImageDecode(format!(
+                "expected an 8 byte header, got {} bytes",
+                bytes.len()
+            )));
+        }
+        let header = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let (width, height) = (header(0), header(4));
+        bytes.drain(..8);
+        Ok(Self {
+            width,
+            height,
+            rgba: bytes,
+        })
+    }
+}
+
+/// Format of a history entry, see `HistoryFilter::format` and `RetentionPolicy::formats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryFormat {
+    Text,
+    Html,
+    Rtf,
+    Image,
+    Files,
+}
+
+/// One representation of the content written by `Clipboard::write`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClipboardItem {
+    Text {
+        text: String,
+    },
+    Html {
+        html: String,
+    },
+    Rtf {
+        rtf: String,
+    },
+    /// Encoded image, in any format the `image` crate can decode (PNG, JPEG, WebP, BMP, ...).
+    Image {
+        bytes: Vec<u8>,
+    },
+    /// Absolute file paths, see `Clipboard::write_files`.
+    Files {
+        paths: Vec<String>,
+    },
+    /// Raw content of a custom format, see `Clipboard::write_custom`.
+    Custom {
+        format: String,
+        bytes: Vec<u8>,
+    },
+}
@@ -0,0 +1,641 @@
+use base64::{engine::general_purpose, Engine as _};
+use clipboard_rs::{common::RustImage, ClipboardHandler};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{atomic::AtomicBool, Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+use tauri::{Emitter, Runtime};
+
+use crate::backend::ClipboardBackend;
+use crate::desktop::{encode_image, AvailableTypes};
+use crate::history::History;
+use crate::subscription::Subscribers;
+use crate::utils::Fnv1aHasher;
+use crate::{Clock, Config};
+
+/// Events emitted by the monitor for a single format, like `plugin:clipboard://text-changed`.
+/// They are only emitted while subscribed with `Clipboard::subscribe_format_events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FormatEvent {
+    Text,
+    Html,
+    Rtf,
+    Files,
+    /// Image encoded in base64.
+    Image,
+    /// Image as an array of bytes.
+    ImageBinary,
+}
+
+impl FormatEvent {
+    fn event_name(&self) -> &'static str {
+        match self {
+            FormatEvent::Text => "text-changed",
+            FormatEvent::Html => "html-changed",
+            FormatEvent::Rtf => "rtf-changed",
+            FormatEvent::Files => "files-changed",
+            FormatEvent::Image => "image-changed",
+            FormatEvent::ImageBinary => "image-changed-binary",
+        }
+    }
+}
+
+/// Payload of the per-format events.
+#[derive(Clone, Serialize)]
+struct ChangedPayload<T> {
+    value: T,
+}
+
+/// Payload of the `something-changed` event.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatedTypes {
+    text: bool,
+    html: bool,
+    rtf: bool,
+    image: bool,
+    image_binary: bool,
+    files: bool,
+}
+
+/// State shared between `Clipboard` and the running monitor.
+#[derive(Default)]
+pub(crate) struct MonitorState {
+    /// Number of subscribers of each [`FormatEvent`].
+    pub format_subscriptions: Mutex<HashMap<FormatEvent, usize>>,
+    /// Fingerprint of the content seen by the monitor on the last change.
+    pub fingerprint: Mutex<Option<u64>>,
+    pub subscribers: Mutex<Subscribers>,
+    pub history: Mutex<History>,
+    /// Fingerprint of the content written by `Clipboard::history_restore`, which must not be
+    /// recorded again.
+    pub restored: Mutex<Option<u64>>,
+    pub sweeper_started: AtomicBool,
+}
+
+/// Hash of the content of every available format. Writing the same content again yields the
+/// same fingerprint.
+pub(crate) fn fingerprint(clipboard: &dyn ClipboardBackend, available: &AvailableTypes) -> u64 {
+    let mut hasher = Fnv1aHasher::default();
+    write_format(
+        &mut hasher,
+        available.text,
+        || clipboard.get_text().ok(),
+        |h, text| h.write_bytes(text.as_bytes()),
+    );
+    write_format(
+        &mut hasher,
+        available.html,
+        || clipboard.get_html().ok(),
+        |h, html| h.write_bytes(html.as_bytes()),
+    );
+    write_format(
+        &mut hasher,
+        available.rtf,
+        || clipboard.get_rich_text().ok(),
+        |h, rtf| h.write_bytes(rtf.as_bytes()),
+    );
+    write_format(
+        &mut hasher,
+        available.files,
+        || clipboard.get_files().ok(),
+        |h, files| {
+            h.write_u64(files.len() as u64);
+            for file in files {
+                h.write_bytes(file.as_bytes());
+            }
+        },
+    );
+    write_format(
+        &mut hasher,
+        available.image,
+        || clipboard.get_image().ok()?.to_rgba8().ok(),
+        |h, image| {
+            h.write_u32(image.width());
+            h.write_u32(image.height());
+            h.write_bytes(image.as_raw());
+        },
+    );
+    hasher.finish()
+}
+
+/// Feed one format to `hasher`, telling apart a missing format, one that could not be read and
+/// its content.
+fn write_format<T>(
+    hasher: &mut Fnv1aHasher,
+    available: bool,
+    read: impl FnOnce() -> Option<T>,
+    write: impl FnOnce(&mut Fnv1aHasher, T),
+) {
+    match available.then(read) {
+        None => hasher.write_u8(0),
+        Some(None) => hasher.write_u8(1),
+        Some(Some(value)) => {
+            hasher.write_u8(2);
+            write(hasher, value);
+        }
+    }
+}
+
+/// Fingerprints are sent to the frontend as hex strings, a `u64` does not fit in a JS number.
+pub(crate) fn format_fingerprint(fingerprint: u64) -> String {
+    format!("{:016x}", fingerprint)
+}
+
+/// Options of a monitor started with `Clipboard::start_monitor_with_options`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonitorOptions {
+    /// Include the clipboard content in every [`ClipboardChangeEvent`].
+    pub include_content: bool,
+    /// Only emit once the clipboard has not changed for this many milliseconds.
+    pub debounce_ms: u64,
+    /// Emit at most one change per this many milliseconds.
+    pub throttle_ms: u64,
+    /// Emit the last change of a throttle window when the window ends, instead of dropping it.
+    pub trailing: bool,
+}
+
+/// Payload of the `clipboard-monitor/update` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardChangeEvent {
+    /// Number of changes seen since the monitor was started, starting at 1.
+    pub sequence: u64,
+    /// Hash of the clipboard content, see `Clipboard::fingerprint`.
+    pub fingerprint: String,
+    pub available: AvailableTypes,
+    /// Only set when the monitor was started with [`MonitorOptions::include_content`].
+    pub content: Option<ClipboardSnapshot>,
+}
+
+/// Content of every known format on the clipboard. Formats that are missing, fail to read or
+/// exceed the configured size limits are `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardSnapshot {
+    pub text: Option<String>,
+    pub html: Option<String>,
+    pub rtf: Option<String>,
+    /// Image encoded as configured in `Config::image_encoding`, in base64.
+    pub image: Option<String>,
+    pub files: Option<Vec<String>>,
+}
+
+/// Options of `Clipboard::read_all`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapshotOptions {
+    /// Leave the image out, it is still reported in `ClipboardContents::available`.
+    pub skip_image: bool,
+    /// Leave out text, HTML and RTF larger than this many bytes, on top of `Config::max_text_size`.
+    pub max_text_size: Option<usize>,
+    /// Leave out an encoded image larger than this many bytes, on top of `Config::max_image_size`.
+    pub max_image_size: Option<usize>,
+}
+
+/// Result of `Clipboard::read_all`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardContents {
+    pub available: AvailableTypes,
+    pub content: ClipboardSnapshot,
+}
+
+impl ClipboardSnapshot {
+    pub(crate) fn read(
+        clipboard: &dyn ClipboardBackend,
+        available: &AvailableTypes,
+        config: &Config,
+    ) -> Self {
+        Self::read_with_options(clipboard, available, config, &SnapshotOptions::default())
+    }
+
+    pub(crate) fn read_with_options(
+        clipboard: &dyn ClipboardBackend,
+        available: &AvailableTypes,
+        config: &Config,
+        options: &SnapshotOptions,
+    ) -> Self {
+        let limit = |limits: [Option<usize>; 2]| limits.into_iter().flatten().min();
+        let text_limit = limit([config.max_text_size, options.max_text_size]).unwrap_or(usize::MAX);
+        let image_limit =
+            limit([config.max_image_size, options.max_image_size]).unwrap_or(usize::MAX);
+        let text = |read: fn(&dyn ClipboardBackend) -> clipboard_rs::Result<String>| {
+            read(clipboard).ok().filter(|text| text.len() <= text_limit)
+        };
+        Self {
+            text: available
+                .text
+                .then(|| text(|clipboard| clipboard.get_text()))
+                .flatten(),
+            html: available
+                .html
+                .then(|| text(|clipboard| clipboard.get_html()))
+                .flatten(),
+            rtf: available
+                .rtf
+                .then(|| text(|clipboard| clipboard.get_rich_text()))
+                .flatten(),
+            image: (available.image && !options.skip_image)
+                .then(|| {
+                    let image = clipboard.get_image().ok()?;
+                    let bytes =
+                        encode_image(&image, config.image_encoding, config.jpeg_quality).ok()?;
+                    (bytes.len() <= image_limit).then(|| general_purpose::STANDARD.encode(bytes))
+                })
+                .flatten(),
+            files: available
+                .files
+                .then(|| clipboard.get_files().ok())
+                .flatten(),
+        }
+    }
+}
+
+/// Reads the clipboard and emits the change events.
+struct MonitorEmitter<R>
+where
+    R: Runtime,
+{
+    app_handle: tauri::AppHandle<R>,
+    clipboard: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+    config: Config,
+    options: MonitorOptions,
+    state: Arc<MonitorState>,
+    sequence: u64,
+}
+
+impl<R> MonitorEmitter<R>
+where
+    R: Runtime,
+{
+    fn emit_format_event<T: Serialize + Clone>(&self, event: FormatEvent, value: T) {
+        let _ = self.app_handle.emit(
+            &self.config.event_name(event.event_name()),
+            ChangedPayload { value },
+        );
+    }
+
+    /// Emit `something-changed` and the subscribed per-format events. Like the frontend
+    /// `listenToClipboard` used to, files take precedence: when files are emitted, text and
+    /// the other formats are not.
+    fn emit_format_events(&self, clipboard: &dyn ClipboardBackend, available: &AvailableTypes) {
+        let subscribed = match self.state.format_subscriptions.lock() {
+            Ok(subscriptions) => subscriptions
+                .iter()
+                .filter(|(_, count)| **count > 0)
+                .map(|(event, _)| *event)
+                .collect::<Vec<_>>(),
+            Err(_) => return,
+        };
+        if subscribed.is_empty() {
+            return;
+        }
+        let _ = self.app_handle.emit(
+            &self.config.event_name("something-changed"),
+            UpdatedTypes {
+                text: available.text,
+                html: available.html,
+                rtf: available.rtf,
+                image: available.image,
+                image_binary: available.image,
+                files: available.files,
+            },
+        );
+        let is_subscribed = |event| subscribed.contains(&event);
+        if is_subscribed(FormatEvent::Files) && available.files {
+            if let Ok(files) = clipboard.get_files() {
+                if !files.is_empty() {
+                    self.emit_format_event(FormatEvent::Files, files);
+                }
+            }
+            return;
+        }
+        if available.image
+            && (is_subscribed(FormatEvent::Image) || is_subscribed(FormatEvent::ImageBinary))
+        {
+            let bytes = clipboard.get_image().ok().and_then(|image| {
+                encode_image(&image, self.config.image_encoding, self.config.jpeg_quality).ok()
+            });
+            if let Some(bytes) = bytes {
+                if is_subscribed(FormatEvent::Image) {
+                    self.emit_format_event(
+                        FormatEvent::Image,
+                        general_purpose::STANDARD.encode(&bytes),
+                    );
+                }
+                if is_subscribed(FormatEvent::ImageBinary) {
+                    self.emit_format_event(FormatEvent::ImageBinary, bytes);
+                }
+            }
+        }
+        if is_subscribed(FormatEvent::Html) && available.html {
+            if let Ok(html) = clipboard.get_html() {
+                self.emit_format_event(FormatEvent::Html, html);
+            }
+        }
+        if is_subscribed(FormatEvent::Rtf) && available.rtf {
+            if let Ok(rtf) = clipboard.get_rich_text() {
+                self.emit_format_event(FormatEvent::Rtf, rtf);
+            }
+        }
+        if is_subscribed(FormatEvent::Text) && available.text {
+            if let Ok(text) = clipboard.get_text() {
+                self.emit_format_event(FormatEvent::Text, text);
+            }
+        }
+    }
+
+    fn record(&self, fingerprint: u64, available: &AvailableTypes, content: ClipboardSnapshot) {
+        let changed = match self.state.history.lock() {
+            Ok(mut history) => {
+                match history.push(format_fingerprint(fingerprint), available.clone(), content) {
+                    Ok(changed) => changed,
+                    Err(_) => return,
+                }
+            }
+            Err(_) => return,
+        };
+        let _ = self
+            .app_handle
+            .emit(&self.config.event_name("history-changed"), changed);
+    }
+
+    fn emit_change(&mut self) {
+        let Ok(clipboard) = self.clipboard.lock() else {
+            return;
+        };
+        let available = AvailableTypes::read(clipboard.as_ref());
+        let fingerprint = fingerprint(clipboard.as_ref(), &available);
+        match self.state.fingerprint.lock() {
+            // some apps write the same content several times per copy
+            Ok(last) if *last == Some(fingerprint) => return,
+            Ok(mut last) => *last = Some(fingerprint),
+            Err(_) => return,
+        }
+        let restored = match self.state.restored.lock() {
+            Ok(mut restored) => restored.take() == Some(fingerprint),
+            Err(_) => false,
+        };
+        let record = self.config.history.enabled && available.any() && !restored;
+        let content = (self.options.include_content || record)
+            .then(|| ClipboardSnapshot::read(clipboard.as_ref(), &available, &self.config));
+        self.emit_format_events(clipboard.as_ref(), &available);
+        drop(clipboard);
+        if record {
+            if let Some(content) = &content {
+                self.record(fingerprint, &available, content.clone());
+            }
+        }
+        let content = content.filter(|_| self.options.include_content);
+        self.sequence += 1;
+        let event = ClipboardChangeEvent {
+            sequence: self.sequence,
+            fingerprint: format_fingerprint(fingerprint),
+            available,
+            content,
+        };
+        let _ = self
+            .app_handle
+            .emit(&self.config.event_name("clipboard-monitor/update"), &event);
+        // called without holding the lock, so callbacks may subscribe or unsubscribe
+        let callbacks = match self.state.subscribers.lock() {
+            Ok(subscribers) => subscribers.callbacks(),
+            Err(_) => return,
+        };
+        for callback in callbacks {
+            callback(&event);
+        }
+    }
+}
+
+/// Decides when clipboard changes are emitted, according to the debounce and throttle windows
+/// of [`MonitorOptions`].
+struct RateLimiter {
+    debounce: Duration,
+    throttle: Duration,
+    trailing: bool,
+    last_emit: Option<Instant>,
+    /// Time of the last change that has not been emitted yet.
+    pending: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(options: &MonitorOptions) -> Self {
+        Self {
+            debounce: Duration::from_millis(options.debounce_ms),
+            throttle: Duration::from_millis(options.throttle_ms),
+            trailing: options.trailing,
+            last_emit: None,
+            pending: None,
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.debounce.is_zero() || !self.throttle.is_zero()
+    }
+
+    fn throttle_end(&self) -> Option<Instant> {
+        self.last_emit.map(|last_emit| last_emit + self.throttle)
+    }
+
+    /// Record a change at `now`. Returns whether it should be emitted right away.
+    fn on_change(&mut self, now: Instant) -> bool {
+        if !self.debounce.is_zero() {
+            self.pending = Some(now);
+            return false;
+        }
+        match self.throttle_end() {
+            Some(throttle_end) if now < throttle_end => {
+                if self.trailing {
+                    self.pending = Some(now);
+                }
+                false
+            }
+            _ => {
+                self.last_emit = Some(now);
+                self.pending = None;
+                true
+            }
+        }
+    }
+
+    /// When the pending change is due, if there is one.
+    fn deadline(&self) -> Option<Instant> {
+        let pending = self.pending?;
+        let debounce_end = pending + self.debounce;
+        Some(match self.throttle_end() {
+            Some(throttle_end) => debounce_end.max(throttle_end),
+            None => debounce_end,
+        })
+    }
+
+    /// Returns whether the pending change is due at `now`, and if so marks it as emitted.
+    fn poll(&mut self, now: Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if deadline <= now => {
+                self.pending = None;
+                self.last_emit = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+struct TimerState {
+    limiter: RateLimiter,
+    stopped: bool,
+}
+
+struct MonitorInner<R>
+where
+    R: Runtime,
+{
+    emitter: Mutex<MonitorEmitter<R>>,
+    timer: Mutex<TimerState>,
+    wake: Condvar,
+    clock: Arc<dyn Clock>,
+}
+
+impl<R> MonitorInner<R>
+where
+    R: Runtime,
+{
+    fn emit_change(&self) {
+        if let Ok(mut emitter) = self.emitter.lock() {
+            emitter.emit_change();
+        }
+    }
+
+    /// Emits the pending change if it is due, called when a simulated clock is advanced.
+    fn poll_timer(&self) {
+        let due = match self.timer.lock() {
+            Ok(mut timer) => !timer.stopped && timer.limiter.poll(self.clock.now()),
+            Err(_) => return,
+        };
+        if due {
+            self.emit_change();
+        }
+    }
+
+    /// Emits delayed changes when they are due, until the monitor is dropped.
+    fn run_timer(&self) {
+        let Ok(mut timer) = self.timer.lock() else {
+            return;
+        };
+        while !timer.stopped {
+            let now = self.clock.now();
+            if timer.limiter.poll(now) {
+                drop(timer);
+                self.emit_change();
+                timer = match self.timer.lock() {
+                    Ok(timer) => timer,
+                    Err(_) => return,
+                };
+                continue;
+            }
+            timer = match timer.limiter.deadline() {
+                Some(deadline) => match self
+                    .wake
+                    .wait_timeout(timer, deadline.saturating_duration_since(now))
+                {
+                    Ok((timer, _)) => timer,
+                    Err(_) => return,
+                },
+                None => match self.wake.wait(timer) {
+                    Ok(timer) => timer,
+                    Err(_) => return,
+                },
+            };
+        }
+    }
+}
+
+/// Clipboard change handler that emits events to the webview.
+pub(crate) struct ClipboardMonitor<R>
+where
+    R: Runtime,
+{
+    inner: Arc<MonitorInner<R>>,
+}
+
+impl<R> ClipboardMonitor<R>
+where
+    R: Runtime,
+{
+    pub fn new(
+        app_handle: tauri::AppHandle<R>,
+        clipboard: Arc<Mutex<Box<dyn ClipboardBackend>>>,
+        config: Config,
+        options: MonitorOptions,
+        state: Arc<MonitorState>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        let limiter = RateLimiter::new(&options);
+        let rate_limited = limiter.is_enabled();
+        let inner = Arc::new(MonitorInner {
+            emitter: Mutex::new(MonitorEmitter {
+                app_handle,
+                clipboard,
+                config,
+                options,
+                state,
+                sequence: 0,
+            }),
+            timer: Mutex::new(TimerState {
+                limiter,
+                stopped: false,
+            }),
+            wake: Condvar::new(),
+            clock,
+        });
+        if rate_limited {
+            let weak = Arc::downgrade(&inner);
+            inner
+                .clock
+                .on_advance(Box::new(move || match weak.upgrade() {
+                    Some(inner) => {
+                        inner.poll_timer();
+                        true
+                    }
+                    None => false,
+                }));
+            let inner = inner.clone();
+            std::thread::spawn(move || inner.run_timer());
+        }
+        Self { inner }
+    }
+}
+
+impl<R> ClipboardHandler for ClipboardMonitor<R>
+where
+    R: Runtime,
+{
+    fn on_clipboard_change(&mut self) {
+        let emit_now = match self.inner.timer.lock() {
+            Ok(mut timer) => timer.limiter.on_change(self.inner.clock.now()),
+            Err(_) => return,
+        };
+        self.inner.wake.notify_one();
+        if emit_now {
+            self.inner.emit_change();
+        }
+    }
+}
+
+impl<R> Drop for ClipboardMonitor<R>
+where
+    R: Runtime,
+{
+    fn drop(&mut self) {
+        if let Ok(mut timer) = self.inner.timer.lock() {
+            timer.stopped = true;
+        }
+        self.inner.wake.notify_one();
+    }
+}
@@ -0,0 +1,400 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+};
+
+use crate::{ClipboardSnapshot, HistoryEntry};
+
+/// Number of characters of context kept around the first match of a highlight.
+const SNIPPET_CONTEXT: usize = 40;
+const SNIPPET_LENGTH: usize = 160;
+
+/// Searchable part of a history entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SearchField {
+    Text,
+    /// HTML with the markup removed.
+    Html,
+    /// RTF with the control words removed.
+    Rtf,
+    /// File paths, one per line.
+    Files,
+}
+
+impl SearchField {
+    pub(crate) const ALL: [SearchField; 4] = [
+        SearchField::Text,
+        SearchField::Html,
+        SearchField::Rtf,
+        SearchField::Files,
+    ];
+
+    /// The plain text of this field in `content`.
+    pub(crate) fn text(self, content: &ClipboardSnapshot) -> Option<String> {
+        match self {
+            SearchField::Text => content.text.clone(),
+            SearchField::Html => content.html.as_deref().map(strip_html),
+            SearchField::Rtf => content.rtf.as_deref().map(strip_rtf),
+            SearchField::Files => content.files.as_ref().map(|files| files.join("\n")),
+        }
+    }
+}
+
+/// Part of a field matching the query.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Highlight {
+    pub field: SearchField,
+    /// Excerpt of the field around the first match.
+    pub snippet: String,
+    /// `[start, end)` of every match in `snippet`, in UTF-16 code units like JS string indices.
+    pub ranges: Vec<[usize; 2]>,
+}
+
+/// A history entry matching a search, see `Clipboard::history_search`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySearchHit {
+    /// The entry, without the payloads stored as blobs like in `history_list`.
+    pub entry: HistoryEntry,
+    pub score: f64,
+    pub highlights: Vec<Highlight>,
+}
+
+/// A page of search results.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistorySearchResults {
+    /// Number of matching entries across all pages.
+    pub total: usize,
+    pub hits: Vec<HistorySearchHit>,
+}
+
+/// Inverted index from terms to the entries containing them. Only terms are kept in memory,
+/// payloads are read back for the hits of the requested page only.
+#[derive(Default)]
+pub(crate) struct SearchIndex {
+    postings: BTreeMap<String, HashMap<u64, u32>>,
+    documents: HashMap<u64, Vec<String>>,
+}
+
+impl SearchIndex {
+    pub fn insert(&mut self, id: u64, terms: HashMap<String, u32>) {
+        self.remove(id);
+        let mut document = Vec::with_capacity(terms.len());
+        for (term, count) in terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(id, count);
+            document.push(term);
+        }
+        self.documents.insert(id, document);
+    }
+
+    pub fn remove(&mut self, id: u64) {
+        for term in self.documents.remove(&id).unwrap_or_default() {
+            if let Some(postings) = self.postings.get_mut(&term) {
+                postings.remove(&id);
+                if postings.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Entries containing every term, as a whole word or as the prefix of one, with their
+    /// tf-idf score. Whole word matches score higher than prefix matches.
+    pub fn search(&self, terms: &[String]) -> Vec<(u64, f64)> {
+        let documents = self.documents.len().max(1) as f64;
+        let mut scores: Option<HashMap<u64, f64>> = None;
+        for term in terms {
+            let mut term_scores = HashMap::<u64, f64>::new();
+            let matches = self
+                .postings
+                .range(term.clone()..)
+                .take_while(|(indexed, _)| indexed.starts_with(term.as_str()));
+            for (indexed, postings) in matches {
+                let idf = (documents / postings.len() as f64).ln() + 1.0;
+                let weight = if indexed == term { 1.0 } else { 0.5 };
+                for (id, count) in postings {
+                    // a term matching several words of an entry counts its best match
+                    let score = (1.0 + f64::from(*count).ln()) * idf * weight;
+                    let best = term_scores.entry(*id).or_default();
+                    *best = best.max(score);
+                }
+            }
+            scores = Some(match scores {
+                None => term_scores,
+                Some(scores) => scores
+                    .into_iter()
+                    .filter_map(|(id, score)| Some((id, score + term_scores.get(&id)?)))
+                    .collect(),
+            });
+        }
+        scores.unwrap_or_default().into_iter().collect()
+    }
+}
+
+/// Lowercase words of `text` with their byte range.
+fn tokens(text: &str) -> impl Iterator<Item = (Range<usize>, String)> + '_ {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while chars.next_if(|(_, c)| !c.is_alphanumeric()).is_some() {}
+        let (start, _) = *chars.peek()?;
+        let mut end = start;
+        while let Some((index, c)) = chars.next_if(|(_, c)| c.is_alphanumeric()) {
+            end = index + c.len_utf8();
+        }
+        Some((start..end, text[start..end].to_lowercase()))
+    })
+}
+
+/// Distinct words of a search query.
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+    for (_, term) in tokens(query) {
+        if !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+/// Occurrences of each word of `text`.
+pub(crate) fn text_terms(text: &str, terms: &mut HashMap<String, u32>) {
+    for (_, term) in tokens(text) {
+        *terms.entry(term).or_default() += 1;
+    }
+}
+
+/// Occurrences of each word in the searchable fields of `content`.
+pub(crate) fn content_terms(content: &ClipboardSnapshot) -> HashMap<String, u32> {
+    let mut terms = HashMap::new();
+    for field in SearchField::ALL {
+        if let Some(text) = field.text(content) {
+            text_terms(&text, &mut terms);
+        }
+    }
+    terms
+}
+
+/// Highlights of `terms` in every searchable field of `content`.
+pub(crate) fn highlights(content: &ClipboardSnapshot, terms: &[String]) -> Vec<Highlight> {
+    SearchField::ALL
+        .into_iter()
+        .filter_map(|field| {
+            let text = field.text(content)?;
+            let matches: Vec<Range<usize>> = tokens(&text)
+                .filter(|(_, token)| terms.iter().any(|term| token.starts_with(term.as_str())))
+                .map(|(range, _)| range)
+                .collect();
+            let first = matches.first()?;
+            let start = char_boundary_before(&text, first.start, SNIPPET_CONTEXT);
+            let end = char_boundary_after(&text, start, SNIPPET_LENGTH);
+            let snippet = &text[start..end];
+            let utf16_offset = |index: usize| snippet[..index - start].encode_utf16().count();
+            let ranges = matches
+                .iter()
+                .filter(|range| range.end <= end)
+                .map(|range| [utf16_offset(range.start), utf16_offset(range.end)])
+                .collect();
+            Some(Highlight {
+                field,
+                snippet: snippet.to_string(),
+                ranges,
+            })
+        })
+        .collect()
+}
+
+/// Byte index `chars` characters before `index`.
+fn char_boundary_before(text: &str, index: usize, chars: usize) -> usize {
+    text[..index]
+        .char_indices()
+        .rev()
+        .nth(chars.saturating_sub(1))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Byte index `chars` characters after `index`.
+fn char_boundary_after(text: &str, index: usize, chars: usize) -> usize {
+    text[index..]
+        .char_indices()
+        .nth(chars)
+        .map_or(text.len(), |(offset, _)| index + offset)
+}
+
+/// Tags that do not separate words, e.g. `wo<b>rd</b>` is one word.
+const INLINE_TAGS: &[&str] = &[
+    "a", "abbr", "b", "code", "em", "font", "i", "mark", "s", "small", "span", "strong", "sub",
+    "sup", "u",
+];
+
+/// Text content of an HTML fragment.
+pub(crate) fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        let tag = &rest[start..];
+        let end = tag.find('>').map_or(tag.len(), |end| end + 1);
+        let inner = tag[1..end].trim_end_matches('>');
+        let closing = inner.starts_with('/');
+        let name = inner
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        rest = &tag[end..];
+        if !closing && (name == "script" || name == "style") {
+            // ASCII lowercasing keeps byte offsets
+            let close = format!("</{}", name);
+            rest = match rest.to_ascii_lowercase().find(&close) {
+                Some(index) => &rest[index..],
+                None => "",
+            };
+        } else if !INLINE_TAGS.contains(&name.as_str()) {
+            text.push(' ');
+        }
+    }
+    text.push_str(&decode_entities(rest));
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = name.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// RTF destinations whose content is not document text.
+const IGNORED_DESTINATIONS: &[&str] = &[
+    "colortbl",
+    "datastore",
+    "fonttbl",
+    "footer",
+    "generator",
+    "header",
+    "info",
+    "latentstyles",
+    "listoverridetable",
+    "listtable",
+    "pict",
+    "rsidtbl",
+    "stylesheet",
+    "themedata",
+    "xmlnstbl",
+];
+
+/// Text content of an RTF document.
+pub(crate) fn strip_rtf(rtf: &str) -> String {
+    let mut text = String::new();
+    let mut chars = rtf.chars().peekable();
+    let mut skipped_groups = Vec::new();
+    let mut skip = false;
+    let mut group_start = false;
+    while let Some(c) = chars.next() {
+        let at_group_start = std::mem::take(&mut group_start);
+        match c {
+            '{' => {
+                skipped_groups.push(skip);
+                group_start = true;
+            }
+            '}' => skip = skipped_groups.pop().unwrap_or(false),
+            '\r' | '\n' => {}
+            '\\' => match chars.next() {
+                Some(c @ ('\\' | '{' | '}')) if !skip => text.push(c),
+                Some('\'') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    if let (false, Ok(byte)) = (skip, u8::from_str_radix(&hex, 16)) {
+                        // code page 1252 matches Latin-1 for letters
+                        text.push(char::from(byte));
+                    }
+                }
+                Some('*') if at_group_start => skip = true,
+                Some('~') if !skip => text.push(' '),
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let mut word = String::from(c);
+                    while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                        word.push(c);
+                    }
+                    let mut parameter = String::new();
+                    if let Some(sign) = chars.next_if_eq(&'-') {
+                        parameter.push(sign);
+                    }
+                    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                        parameter.push(digit);
+                    }
+                    chars.next_if_eq(&' ');
+                    if at_group_start && IGNORED_DESTINATIONS.contains(&word.as_str()) {
+                        skip = true;
+                    }
+                    if skip {
+                        continue;
+                    }
+                    match word.as_str() {
+                        "par" | "line" | "sect" | "page" => text.push('\n'),
+                        "tab" | "cell" => text.push('\t'),
+                        "u" => {
+                            let code = parameter.parse::<i32>().unwrap_or_default();
+                            // negative for code points above 32767
+                            let code = if code < 0 { code + 65536 } else { code };
+                            if let Some(c) = char::from_u32(code as u32) {
+                                text.push(c);
+                            }
+                            // ANSI fallback for readers without unicode support
+                            chars.next_if_eq(&'?');
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            },
+            c if !skip => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
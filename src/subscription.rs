@@ -0,0 +1,116 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex, Weak},
+    task::{Context, Poll, Waker},
+};
+
+use futures_core::Stream;
+
+use crate::monitor::{ClipboardChangeEvent, MonitorState};
+
+pub(crate) type Callback = Arc<dyn Fn(&ClipboardChangeEvent) + Send + Sync>;
+
+/// Rust callbacks registered with `Clipboard::subscribe`.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    next_id: usize,
+    callbacks: Vec<(usize, Callback)>,
+}
+
+impl Subscribers {
+    pub fn add(&mut self, callback: Callback) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.callbacks.push((id, callback));
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) {
+        self.callbacks.retain(|(callback_id, _)| *callback_id != id);
+    }
+
+    pub fn callbacks(&self) -> Vec<Callback> {
+        self.callbacks
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect()
+    }
+}
+
+/// Handle returned by `Clipboard::subscribe`. The callback is unregistered when it is dropped.
+#[must_use = "the subscription is cancelled when the handle is dropped"]
+pub struct Subscription {
+    id: usize,
+    state: Weak<MonitorState>,
+}
+
+impl Subscription {
+    pub(crate) fn new(id: usize, state: &Arc<MonitorState>) -> Self {
+        Self {
+            id,
+            state: Arc::downgrade(state),
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(state) = self.state.upgrade() {
+            if let Ok(mut subscribers) = state.subscribers.lock() {
+                subscribers.remove(self.id);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct WatchQueue {
+    events: VecDeque<ClipboardChangeEvent>,
+    waker: Option<Waker>,
+}
+
+impl WatchQueue {
+    pub fn push(queue: &Mutex<WatchQueue>, event: &ClipboardChangeEvent) {
+        if let Ok(mut queue) = queue.lock() {
+            queue.events.push_back(event.clone());
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Stream of clipboard changes returned by `Clipboard::watch`. It never ends on its own, drop it
+/// to unsubscribe.
+#[must_use = "streams do nothing unless polled"]
+pub struct ClipboardWatch {
+    queue: Arc<Mutex<WatchQueue>>,
+    _subscription: Subscription,
+}
+
+impl ClipboardWatch {
+    pub(crate) fn new(queue: Arc<Mutex<WatchQueue>>, subscription: Subscription) -> Self {
+        Self {
+            queue,
+            _subscription: subscription,
+        }
+    }
+}
+
+impl Stream for ClipboardWatch {
+    type Item = ClipboardChangeEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Ok(mut queue) = self.queue.lock() else {
+            return Poll::Ready(None);
+        };
+        match queue.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
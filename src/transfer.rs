@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tauri::ipc::{Channel, InvokeResponseBody};
+
+use crate::{Error, ImageReadOptions, Result};
+
+/// Chunks sent by `Clipboard::read_chunked` when `ChunkedReadOptions::chunk_size` is not set.
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Chunked writes that receive nothing for this long are dropped, e.g. after a webview reload.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Payload of a chunked transfer. Text formats are transferred as UTF-8, images encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferFormat {
+    Text,
+    Html,
+    Rtf,
+    Image,
+}
+
+/// Options of `Clipboard::read_chunked`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ChunkedReadOptions {
+    /// Maximum size in bytes of a chunk, [`DEFAULT_CHUNK_SIZE`] if not set.
+    pub chunk_size: Option<usize>,
+    /// Encoding of a [`TransferFormat::Image`] payload.
+    pub image: ImageReadOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgress {
+    pub id: u64,
+    /// Bytes transferred so far.
+    pub transferred: u64,
+    pub size: u64,
+}
+
+/// Messages of a chunked read, sent as JSON between the raw chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data", rename_all = "camelCase")]
+pub enum TransferEvent {
+    /// Sent first, `size` is the total number of bytes of the chunks that follow.
+    Started {
+        id: u64,
+        size: u64,
+    },
+    /// Sent after every chunk.
+    Progress(TransferProgress),
+    Finished {
+        id: u64,
+    },
+    /// Sent instead of `Finished` when the transfer was cancelled with
+    /// `Clipboard::cancel_transfer`.
+    Cancelled {
+        id: u64,
+    },
+}
+
+struct PendingWrite {
+    format: TransferFormat,
+    size: u64,
+    bytes: Vec<u8>,
+    last_activity: Instant,
+}
+
+/// Chunked transfers in progress.
+#[derive(Default)]
+pub(crate) struct Transfers {
+    next_id: AtomicU64,
+    /// Cancellation flags of the reads.
+    reads: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+    writes: Mutex<HashMap<u64, PendingWrite>>,
+}
+
+impl Transfers {
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Send `payload` through `channel` from a background thread, returning the transfer id.
+    pub fn start_read(
+        self: &Arc<Self>,
+        payload: Vec<u8>,
+        chunk_size: usize,
+        channel: Channel,
+    ) -> Result<u64> {
+        let id = self.next_id();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.reads.lock()?.insert(id, cancelled.clone());
+        let transfers = self.clone();
+        std::thread::spawn(move || {
+            send_chunks(id, &payload, chunk_size.max(1), &channel, &cancelled);
+            if let Ok(mut reads) = transfers.reads.lock() {
+                reads.remove(&id);
+            }
+        });
+        Ok(id)
+    }
+
+    pub fn begin_write(&self, format: TransferFormat, size: u64, now: Instant) -> Result<u64> {
+        let id = self.next_id();
+        let mut writes = self.writes.lock()?;
+        evict_stale(&mut writes, now);
+        writes.insert(
+            id,
+            PendingWrite {
+                format,
+                size,
+                bytes: Vec::new(),
+                last_activity: now,
+            },
+        );
+        Ok(id)
+    }
+
+    pub fn append(&self, id: u64, chunk: &[u8], now: Instant) -> Result<TransferProgress> {
+        let mut writes = self.writes.lock()?;
+        evict_stale(&mut writes, now);
+        let write = writes.get_mut(&id).ok_or(Error::TransferNotFound(id))?;
+        let transferred = (write.bytes.len() + chunk.len()) as u64;
+        if transferred > write.size {
+            return Err(Error::InvalidRequest(format!(
+                "transfer {} received {} bytes, more than the {} announced",
+                id, transferred, write.size
+            )));
+        }
+        write.bytes.extend_from_slice(chunk);
+        write.last_activity = now;
+        Ok(TransferProgress {
+            id,
+            transferred,
+            size: write.size,
+        })
+    }
+
+    /// End the write `id`, returning its payload once every announced byte was received.
+    pub fn finish_write(&self, id: u64) -> Result<(TransferFormat, Vec<u8>)> {
+        let write = self
+            .writes
+            .lock()?
+            .remove(&id)
+            .ok_or(Error::TransferNotFound(id))?;
+        if write.bytes.len() as u64 != write.size {
+            return Err(Error::InvalidRequest(format!(
+                "transfer {} received {} of {} bytes",
+                id,
+                write.bytes.len(),
+                write.size
+            )));
+        }
+        Ok((write.format, write.bytes))
+    }
+
+    /// Stop the read `id` before its next chunk, or drop what the write `id` received.
+    pub fn cancel(&self, id: u64) -> Result<()> {
+        if let Some(cancelled) = self.reads.lock()?.get(&id) {
+            cancelled.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        self.writes
+            .lock()?
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(Error::TransferNotFound(id))
+    }
+}
+
+fn evict_stale(writes: &mut HashMap<u64, PendingWrite>, now: Instant) {
+    writes.retain(|_, write| now.saturating_duration_since(write.last_activity) < WRITE_TIMEOUT);
+}
+
+fn send_chunks(
+    id: u64,
+    payload: &[u8],
+    chunk_size: usize,
+    channel: &Channel,
+    cancelled: &AtomicBool,
+) {
+    let size = payload.len() as u64;
+    let send_event = |event: TransferEvent| match serde_json::to_string(&event) {
+        Ok(json) => channel.send(InvokeResponseBody::Json(json)).is_ok(),
+        Err(_) => false,
+    };
+    if !send_event(TransferEvent::Started { id, size }) {
+        return;
+    }
+    let mut transferred = 0;
+    for chunk in payload.chunks(chunk_size) {
+        if cancelled.load(Ordering::SeqCst) {
+            send_event(TransferEvent::Cancelled { id });
+            return;
+        }
+        // stop once the webview is gone
+        if channel
+            .send(InvokeResponseBody::Raw(chunk.to_vec()))
+            .is_err()
+        {
+            return;
+        }
+        transferred += chunk.len() as u64;
+        if !send_event(TransferEvent::Progress(TransferProgress {
+            id,
+            transferred,
+            size,
+        })) {
+            return;
+        }
+    }
+    send_event(TransferEvent::Finished { id });
+}
@@ -1 +1,43 @@
+/// 64-bit FNV-1a hasher. Unlike `DefaultHasher` its output is stable across Rust releases and
+/// platforms, so hashes can be persisted. It deliberately does not implement `std::hash::Hasher`:
+/// std `Hash` impls write `usize` length prefixes, whose width depends on the platform, so
+/// values are fed as explicitly encoded bytes instead.
+pub(crate) struct Fnv1aHasher(u64);
 
+impl Default for Fnv1aHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv1aHasher {
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.write(&[value]);
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Write `bytes` after their length as a `u64`, so consecutive values cannot run into each
+    /// other.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        self.write(bytes);
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use tauri::Manager;
+use tauri_plugin_clipboard::{Builder, Clipboard, Error, WatchHandle};
+
+mod common;
+
+use common::FailingBackend;
+
+#[test]
+fn builder_uses_custom_backend() {
+    let app = tauri::test::mock_builder()
+        .plugin(Builder::new().backend(FailingBackend).build())
+        .build(tauri::test::mock_context(tauri::test::noop_assets()))
+        .unwrap();
+    let clipboard = app.state::<Clipboard>();
+    assert!(matches!(
+        clipboard.write_text("a".into()),
+        Err(Error::Backend(_))
+    ));
+    assert!(!clipboard.has_text().unwrap());
+}
+
+#[test]
+fn watch_handle_releases_guard_when_stopped() {
+    struct Guard(Arc<AtomicBool>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let released = Arc::new(AtomicBool::new(false));
+    let handle = WatchHandle::new(Guard(released.clone()));
+    assert!(!released.load(Ordering::SeqCst));
+    handle.stop();
+    assert!(released.load(Ordering::SeqCst));
+
+    let released = Arc::new(AtomicBool::new(false));
+    drop(WatchHandle::new(Guard(released.clone())));
+    assert!(released.load(Ordering::SeqCst));
+}
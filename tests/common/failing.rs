@@ -0,0 +1,81 @@
+use tauri_plugin_clipboard::clipboard_rs::{
+    ClipboardContent, ClipboardHandler, ContentFormat, Result, RustImageData,
+};
+use tauri_plugin_clipboard::{ClipboardBackend, WatchHandle};
+
+/// A backend whose every operation fails, like a clipboard held by another process.
+pub struct FailingBackend;
+
+fn unavailable<T>() -> Result<T> {
+    Err("clipboard is owned by another process".into())
+}
+
+impl ClipboardBackend for FailingBackend {
+    fn available_formats(&self) -> Result<Vec<String>> {
+        unavailable()
+    }
+
+    fn has(&self, _format: ContentFormat) -> bool {
+        false
+    }
+
+    fn get_buffer(&self, _format: &str) -> Result<Vec<u8>> {
+        unavailable()
+    }
+
+    fn get_text(&self) -> Result<String> {
+        unavailable()
+    }
+
+    fn get_html(&self) -> Result<String> {
+        unavailable()
+    }
+
+    fn get_rich_text(&self) -> Result<String> {
+        unavailable()
+    }
+
+    fn get_image(&self) -> Result<RustImageData> {
+        unavailable()
+    }
+
+    fn get_files(&self) -> Result<Vec<String>> {
+        unavailable()
+    }
+
+    fn set_text(&self, _text: String) -> Result<()> {
+        unavailable()
+    }
+
+    fn set_html(&self, _html: String) -> Result<()> {
+        unavailable()
+    }
+
+    fn set_rich_text(&self, _rtf: String) -> Result<()> {
+        unavailable()
+    }
+
+    fn set_image(&self, _image: RustImageData) -> Result<()> {
+        unavailable()
+    }
+
+    fn set_files(&self, _files: Vec<String>) -> Result<()> {
+        unavailable()
+    }
+
+    fn set_buffer(&self, _format: &str, _buffer: Vec<u8>) -> Result<()> {
+        unavailable()
+    }
+
+    fn set(&self, _contents: Vec<ClipboardContent>) -> Result<()> {
+        unavailable()
+    }
+
+    fn clear(&self) -> Result<()> {
+        unavailable()
+    }
+
+    fn watch(&self, _handler: Box<dyn ClipboardHandler + Send>) -> Result<WatchHandle> {
+        unavailable()
+    }
+}
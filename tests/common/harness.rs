@@ -0,0 +1,110 @@
+use std::{
+    sync::mpsc::{self, Receiver},
+    time::Duration,
+};
+
+use tauri::Listener;
+use tauri_plugin_clipboard::{
+    clipboard_rs, Clipboard, ClipboardBackend, Config, MockClipboard, MockClock, MonitorOptions,
+};
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A running monitor on a [`MockClipboard`], timed by a [`MockClock`].
+pub struct Harness {
+    pub app: tauri::App<tauri::test::MockRuntime>,
+    pub clipboard: Clipboard,
+    pub backend: MockClipboard,
+    pub clock: MockClock,
+    updates: Receiver<String>,
+}
+
+impl Harness {
+    pub fn start(options: MonitorOptions) -> Self {
+        Self::with_config(Config::default(), options)
+    }
+
+    pub fn with_config(config: Config, options: MonitorOptions) -> Self {
+        let app = tauri::test::mock_app();
+        let backend = MockClipboard::new();
+        let clock = MockClock::new();
+        let clipboard =
+            Clipboard::with_config(Box::new(backend.clone()), config).with_clock(clock.clone());
+        let (sender, updates) = mpsc::channel();
+        app.listen_any(
+            "plugin:clipboard://clipboard-monitor/update",
+            move |event| {
+                let _ = sender.send(event.payload().to_string());
+            },
+        );
+        clipboard
+            .start_monitor_with_options(app.handle().clone(), options)
+            .unwrap();
+        Self {
+            app,
+            clipboard,
+            backend,
+            clock,
+            updates,
+        }
+    }
+
+    /// Payloads of the `plugin:clipboard://{event}` events.
+    pub fn listen(&self, event: &str) -> Receiver<serde_json::Value> {
+        let (sender, receiver) = mpsc::channel();
+        self.app
+            .listen_any(format!("plugin:clipboard://{}", event), move |event| {
+                let _ = sender.send(serde_json::from_str(event.payload()).unwrap());
+            });
+        receiver
+    }
+
+    pub fn copy(&self, text: &str) {
+        self.backend.set_text(text.to_string()).unwrap();
+    }
+
+    /// Copy `text` and wait until the monitor has handled the change.
+    pub fn copy_handled(&self, text: &str) {
+        self.copy_with(|backend| backend.set_text(text.to_string()));
+    }
+
+    /// Write with `write` and wait until the monitor has handled the change.
+    pub fn copy_with(&self, write: impl FnOnce(&MockClipboard) -> clipboard_rs::Result<()>) {
+        let (sender, changed) = mpsc::channel();
+        let _subscription = self
+            .clipboard
+            .subscribe(move |_| {
+                let _ = sender.send(());
+            })
+            .unwrap();
+        write(&self.backend).unwrap();
+        changed.recv_timeout(TIMEOUT).unwrap();
+    }
+
+    pub fn expect_update(&self) {
+        self.next_update();
+    }
+
+    /// Payload of the next `clipboard-monitor/update` event.
+    pub fn next_update(&self) -> serde_json::Value {
+        let payload = self
+            .updates
+            .recv_timeout(TIMEOUT)
+            .expect("expected a clipboard update");
+        serde_json::from_str(&payload).unwrap()
+    }
+
+    pub fn expect_no_update(&self) {
+        self.backend.settle();
+        assert!(
+            self.updates.try_recv().is_err(),
+            "unexpected clipboard update"
+        );
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = self.clipboard.stop_monitor(self.app.handle().clone());
+    }
+}
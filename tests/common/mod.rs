@@ -0,0 +1,11 @@
+//! Fixtures shared by the integration tests. Every test crate includes this module and only
+//! uses part of it.
+#![allow(dead_code, unused_imports)]
+
+mod failing;
+#[cfg(feature = "mock")]
+mod harness;
+
+pub use failing::FailingBackend;
+#[cfg(feature = "mock")]
+pub use harness::{Harness, TIMEOUT};
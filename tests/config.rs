@@ -0,0 +1,52 @@
+#![cfg(feature = "mock")]
+
+use std::time::Duration;
+use tauri::Manager;
+
+use tauri_plugin_clipboard::{Builder, Clipboard, ImageEncoding, MockClipboard, RetentionPolicy};
+
+fn app(builder: Builder, config: serde_json::Value) -> tauri::App<tauri::test::MockRuntime> {
+    let mut context = tauri::test::mock_context(tauri::test::noop_assets());
+    context
+        .config_mut()
+        .plugins
+        .0
+        .insert("clipboard".to_string(), config);
+    tauri::test::mock_builder()
+        .plugin(builder.backend(MockClipboard::new()).build())
+        .build(context)
+        .unwrap()
+}
+
+#[test]
+fn builder_overrides_json_config() {
+    let app = app(
+        Builder::new()
+            .max_text_size(8)
+            .image_encoding(ImageEncoding::Webp),
+        serde_json::json!({
+            "maxTextSize": 1024,
+            "imageEncoding": "jpeg",
+            "jpegQuality": 50,
+            "eventPrefix": "clipboard://",
+        }),
+    );
+    let config = &app.state::<Clipboard>().config;
+    assert_eq!(config.max_text_size, Some(8));
+    assert_eq!(config.image_encoding, ImageEncoding::Webp);
+    // not set on the builder, so the JSON config applies
+    assert_eq!(config.jpeg_quality, 50);
+    assert_eq!(config.event_prefix, "clipboard://");
+    assert_eq!(config.max_image_size, None);
+}
+
+#[test]
+fn sweep_interval_has_a_minimum() {
+    let policy = |sweep_interval_ms| RetentionPolicy {
+        sweep_interval_ms,
+        ..Default::default()
+    };
+    assert_eq!(policy(0).sweep_interval(), Duration::from_secs(1));
+    assert_eq!(policy(10).sweep_interval(), Duration::from_secs(1));
+    assert_eq!(policy(90_000).sweep_interval(), Duration::from_secs(90));
+}
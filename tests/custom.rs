@@ -0,0 +1,94 @@
+#![cfg(feature = "mock")]
+
+use tauri_plugin_clipboard::{
+    Clipboard, ClipboardBackend, ClipboardItem, Config, Error, MockClipboard,
+};
+
+const FORMAT: &str = "application/x-test+json";
+
+fn open() -> (Clipboard, MockClipboard) {
+    let backend = MockClipboard::new();
+    (Clipboard::new(Box::new(backend.clone())), backend)
+}
+
+#[test]
+fn write_and_read_custom_format() {
+    let (clipboard, _backend) = open();
+    clipboard
+        .write_custom(FORMAT.into(), br#"{"id":1}"#.to_vec())
+        .unwrap();
+    assert!(clipboard.has_custom(FORMAT.into()).unwrap());
+    assert!(!clipboard.has_custom("application/x-other".into()).unwrap());
+    assert!(!clipboard.has_text().unwrap());
+    assert_eq!(
+        clipboard.read_custom(FORMAT.into()).unwrap(),
+        br#"{"id":1}"#
+    );
+    assert_eq!(clipboard.list_formats().unwrap(), [FORMAT]);
+}
+
+#[test]
+fn custom_format_next_to_text() {
+    let (clipboard, backend) = open();
+    clipboard
+        .write(vec![
+            ClipboardItem::Text {
+                text: "item 1".into(),
+            },
+            ClipboardItem::Custom {
+                format: FORMAT.into(),
+                bytes: br#"{"id":1}"#.to_vec(),
+            },
+        ])
+        .unwrap();
+    assert_eq!(backend.get_text().unwrap(), "item 1");
+    assert_eq!(
+        clipboard.read_custom(FORMAT.into()).unwrap(),
+        br#"{"id":1}"#
+    );
+    assert_eq!(clipboard.list_formats().unwrap(), ["text/plain", FORMAT]);
+}
+
+#[test]
+fn missing_custom_format() {
+    let (clipboard, backend) = open();
+    assert!(matches!(
+        clipboard.read_custom(FORMAT.into()),
+        Err(Error::EmptyClipboard)
+    ));
+    backend.set_text("text".into()).unwrap();
+    assert!(matches!(
+        clipboard.read_custom(FORMAT.into()),
+        Err(Error::FormatUnavailable(format)) if format == FORMAT
+    ));
+}
+
+#[test]
+fn custom_format_size_limit() {
+    let backend = MockClipboard::new();
+    let clipboard = Clipboard::with_config(
+        Box::new(backend.clone()),
+        Config {
+            max_image_size: Some(4),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        clipboard.write_custom(FORMAT.into(), b"12345".to_vec()),
+        Err(Error::PayloadTooLarge { size: 5, limit: 4 })
+    ));
+    assert!(matches!(
+        clipboard.write(vec![ClipboardItem::Custom {
+            format: FORMAT.into(),
+            bytes: b"12345".to_vec(),
+        }]),
+        Err(Error::PayloadTooLarge { size: 5, limit: 4 })
+    ));
+    assert!(backend.available_formats().unwrap().is_empty());
+
+    backend.set_buffer(FORMAT, b"12345".to_vec()).unwrap();
+    assert!(matches!(
+        clipboard.read_custom(FORMAT.into()),
+        Err(Error::PayloadTooLarge { size: 5, limit: 4 })
+    ));
+}
@@ -0,0 +1,100 @@
+use std::io::Cursor;
+
+use tauri_plugin_clipboard::{Clipboard, Error};
+
+mod common;
+
+use common::FailingBackend;
+
+fn png_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::RgbaImage::new(2, 2)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn write_image_binary_reports_backend_error() {
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    let err = clipboard.write_image_binary(png_bytes()).unwrap_err();
+    assert!(matches!(err, Error::Backend(_)));
+}
+
+#[test]
+fn write_image_binary_reports_decode_error() {
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    let err = clipboard.write_image_binary(vec![1, 2, 3]).unwrap_err();
+    assert!(matches!(err, Error::ImageDecode(_)));
+}
+
+#[test]
+fn clear_reports_backend_error() {
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    assert!(matches!(clipboard.clear(), Err(Error::Backend(_))));
+}
+
+#[test]
+fn clear_reports_poisoned_lock() {
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    let lock = clipboard.clipboard.clone();
+    let _ = std::thread::spawn(move || {
+        let _guard = lock.lock().unwrap();
+        panic!("poison the clipboard lock");
+    })
+    .join();
+    assert!(matches!(clipboard.clear(), Err(Error::LockPoisoned)));
+}
+
+#[test]
+fn start_monitor_reports_unavailable_watcher() {
+    let app = tauri::test::mock_app();
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    let err = clipboard.start_monitor(app.handle().clone()).unwrap_err();
+    assert!(matches!(err, Error::BackendUnavailable(_)));
+    assert!(!clipboard.is_monitor_running().unwrap());
+}
+
+#[test]
+fn read_reports_empty_clipboard() {
+    let clipboard = Clipboard::new(Box::new(FailingBackend));
+    assert!(matches!(clipboard.read_text(), Err(Error::EmptyClipboard)));
+}
+
+#[test]
+fn errors_serialize_as_kind_and_message() {
+    let cases = [
+        (
+            Error::EmptyClipboard,
+            "emptyClipboard",
+            "Clipboard is empty",
+        ),
+        (
+            Error::FormatUnavailable("html".into()),
+            "formatUnavailable",
+            "Clipboard has no html content",
+        ),
+        (
+            Error::PayloadTooLarge { size: 10, limit: 8 },
+            "payloadTooLarge",
+            "Payload of 10 bytes exceeds the limit of 8 bytes",
+        ),
+        (
+            Error::HistoryEntryNotFound(3),
+            "historyEntryNotFound",
+            "No history entry with id 3",
+        ),
+        (
+            Error::Io(std::io::Error::new(std::io::ErrorKind::Other, "disk full")),
+            "io",
+            "disk full",
+        ),
+        (Error::Backend("busy".into()), "backend", "busy"),
+    ];
+    for (err, kind, message) in cases {
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "kind": kind, "message": message })
+        );
+    }
+}
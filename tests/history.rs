@@ -0,0 +1,690 @@
+#![cfg(feature = "mock")]
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use tauri::Listener;
+use tauri_plugin_clipboard::{
+    clipboard_rs::ClipboardContent, Clipboard, ClipboardBackend, Config, FormatRetention,
+    HistoryConfig, HistoryFilter, HistoryFormat, MockClipboard, MonitorOptions, RetentionPolicy,
+    SearchField,
+};
+
+mod common;
+
+use common::{Harness, TIMEOUT};
+
+fn history_config(max_entries: usize) -> HistoryConfig {
+    HistoryConfig {
+        enabled: true,
+        max_entries,
+        ..Default::default()
+    }
+}
+
+/// Directory removed again when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let directory = std::env::temp_dir().join(format!(
+            "tauri-plugin-clipboard-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        Self(directory)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn persisted_config(directory: &TempDir) -> HistoryConfig {
+    HistoryConfig {
+        persist: true,
+        directory: Some(directory.0.clone()),
+        ..history_config(10)
+    }
+}
+
+fn open(history: &HistoryConfig) -> Clipboard {
+    let config = Config {
+        history: history.clone(),
+        ..Default::default()
+    };
+    Clipboard::with_config(Box::new(MockClipboard::new()), config)
+}
+
+/// A running monitor recording into `history`.
+fn monitored(history: &HistoryConfig) -> Harness {
+    let config = Config {
+        history: history.clone(),
+        ..Default::default()
+    };
+    Harness::with_config(config, MonitorOptions::default())
+}
+
+fn texts(clipboard: &Clipboard) -> Vec<String> {
+    clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.content.text.unwrap_or_default())
+        .collect()
+}
+
+fn log_path(history: &HistoryConfig) -> PathBuf {
+    history.directory.as_ref().unwrap().join("history.log")
+}
+
+fn blob_count(directory: &Path) -> usize {
+    fs::read_dir(directory.join("blobs")).unwrap().count()
+}
+
+#[test]
+fn keeps_last_entries_newest_first() {
+    let monitored = monitored(&history_config(2));
+    for text in ["a", "b", "c"] {
+        monitored.copy_handled(text);
+    }
+    assert_eq!(texts(&monitored.clipboard), ["c", "b"]);
+}
+
+#[test]
+fn copying_known_content_moves_it_to_the_top() {
+    let monitored = monitored(&history_config(10));
+    for text in ["a", "b", "a"] {
+        monitored.copy_handled(text);
+    }
+    assert_eq!(texts(&monitored.clipboard), ["a", "b"]);
+}
+
+#[test]
+fn delete_and_clear() {
+    let monitored = monitored(&history_config(10));
+    for text in ["a", "b", "c"] {
+        monitored.copy_handled(text);
+    }
+    let clipboard = &monitored.clipboard;
+    let app = monitored.app.handle();
+    let newest = clipboard.history_list(&HistoryFilter::default()).unwrap()[0].id;
+    assert_eq!(
+        clipboard
+            .history_get(newest)
+            .unwrap()
+            .content
+            .text
+            .as_deref(),
+        Some("c")
+    );
+    clipboard.history_delete(app.clone(), newest).unwrap();
+    assert!(clipboard.history_get(newest).is_err());
+    assert_eq!(texts(clipboard), ["b", "a"]);
+
+    clipboard.history_clear(app.clone()).unwrap();
+    assert!(clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn persisted_history_survives_restart() {
+    let directory = TempDir::new("restart");
+    let history = persisted_config(&directory);
+    {
+        let monitored = monitored(&history);
+        for text in ["a", "b", "c"] {
+            monitored.copy_handled(text);
+        }
+        let oldest = monitored
+            .clipboard
+            .history_list(&HistoryFilter::default())
+            .unwrap()[2]
+            .id;
+        monitored
+            .clipboard
+            .history_delete(monitored.app.handle().clone(), oldest)
+            .unwrap();
+    }
+    assert_eq!(texts(&open(&history)), ["c", "b"]);
+}
+
+#[test]
+fn large_payloads_are_stored_as_blobs() {
+    let directory = TempDir::new("blobs");
+    let history = HistoryConfig {
+        blob_threshold: 4,
+        ..persisted_config(&directory)
+    };
+    {
+        let monitored = monitored(&history);
+        monitored.copy_handled("abc");
+        monitored.copy_handled("a long text");
+    }
+    assert_eq!(blob_count(&directory.0), 1);
+
+    let clipboard = open(&history);
+    let entries = clipboard.history_list(&HistoryFilter::default()).unwrap();
+    assert_eq!(entries[0].content.text, None);
+    assert_eq!(entries[1].content.text.as_deref(), Some("abc"));
+    let entry = clipboard.history_get(entries[0].id).unwrap();
+    assert_eq!(entry.content.text.as_deref(), Some("a long text"));
+
+    let app = tauri::test::mock_app();
+    clipboard.history_clear(app.handle().clone()).unwrap();
+    assert_eq!(blob_count(&directory.0), 0);
+}
+
+#[test]
+fn ids_are_not_reused_after_restart() {
+    let directory = TempDir::new("ids");
+    let history = HistoryConfig {
+        compact_threshold: 1,
+        ..persisted_config(&directory)
+    };
+    let deleted = {
+        let monitored = monitored(&history);
+        monitored.copy_handled("a");
+        monitored.copy_handled("b");
+        let newest = monitored
+            .clipboard
+            .history_list(&HistoryFilter::default())
+            .unwrap()[0]
+            .id;
+        monitored
+            .clipboard
+            .history_delete(monitored.app.handle().clone(), newest)
+            .unwrap();
+        monitored
+            .clipboard
+            .history_clear(monitored.app.handle().clone())
+            .unwrap();
+        newest
+    };
+    // the log was compacted down to no entries at all
+    let log = fs::read_to_string(log_path(&history)).unwrap();
+    assert!(!log.contains(r#""op":"add""#), "{}", log);
+    let monitored = monitored(&history);
+    monitored.copy_handled("c");
+    let entries = monitored
+        .clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap();
+    assert!(entries[0].id > deleted, "id {} was reused", entries[0].id);
+}
+
+#[test]
+fn unreferenced_blobs_are_removed_on_load() {
+    let directory = TempDir::new("orphans");
+    let history = HistoryConfig {
+        blob_threshold: 4,
+        ..persisted_config(&directory)
+    };
+    {
+        let monitored = monitored(&history);
+        monitored.copy_handled("a long text");
+    }
+    // a blob written right before a crash, without its record
+    fs::write(directory.0.join("blobs").join("99-text"), "orphan").unwrap();
+    assert_eq!(blob_count(&directory.0), 2);
+
+    let clipboard = open(&history);
+    assert_eq!(
+        clipboard
+            .history_list(&HistoryFilter::default())
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(blob_count(&directory.0), 1);
+}
+
+#[test]
+fn truncated_log_is_recovered() {
+    let directory = TempDir::new("truncated");
+    let history = persisted_config(&directory);
+    {
+        let monitored = monitored(&history);
+        for text in ["a", "b", "c"] {
+            monitored.copy_handled(text);
+        }
+    }
+    // simulate a crash in the middle of writing the last record
+    let log = fs::OpenOptions::new()
+        .write(true)
+        .open(log_path(&history))
+        .unwrap();
+    log.set_len(log.metadata().unwrap().len() - 5).unwrap();
+    drop(log);
+
+    {
+        let monitored = monitored(&history);
+        assert_eq!(texts(&monitored.clipboard), ["b", "a"]);
+        monitored.copy_handled("d");
+    }
+    assert_eq!(texts(&open(&history)), ["d", "b", "a"]);
+}
+
+#[test]
+fn corrupt_record_keeps_newer_history() {
+    let directory = TempDir::new("corrupt");
+    let history = persisted_config(&directory);
+    {
+        let monitored = monitored(&history);
+        for text in ["a", "b", "c"] {
+            monitored.copy_handled(text);
+        }
+    }
+    let log = fs::read_to_string(log_path(&history)).unwrap();
+    let mut lines: Vec<&str> = log.lines().collect();
+    lines[1] = "{\"op\":\"add\",\"entry\":";
+    fs::write(log_path(&history), lines.join("\n") + "\n").unwrap();
+
+    {
+        let monitored = monitored(&history);
+        assert_eq!(texts(&monitored.clipboard), ["c", "a"]);
+        monitored.copy_handled("d");
+    }
+    assert_eq!(texts(&open(&history)), ["d", "c", "a"]);
+}
+
+#[test]
+fn log_is_compacted() {
+    let directory = TempDir::new("compaction");
+    let history = HistoryConfig {
+        max_entries: 2,
+        compact_threshold: 1,
+        ..persisted_config(&directory)
+    };
+    {
+        let monitored = monitored(&history);
+        for text in ["a", "b", "c", "d", "e"] {
+            monitored.copy_handled(text);
+        }
+    }
+    // 5 additions and 3 evictions were written
+    let records = fs::read_to_string(log_path(&history))
+        .unwrap()
+        .lines()
+        .count();
+    assert!(records < 8, "log has {} records", records);
+    assert_eq!(texts(&open(&history)), ["e", "d"]);
+}
+
+fn search_texts(clipboard: &Clipboard, query: &str) -> Vec<String> {
+    clipboard
+        .history_search(query, 0, 20)
+        .unwrap()
+        .hits
+        .into_iter()
+        .map(|hit| hit.entry.content.text.unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn search_ranks_whole_words_first() {
+    let monitored = monitored(&history_config(10));
+    for text in ["clipboard manager", "a clip", "unrelated", "clipping clips"] {
+        monitored.copy_handled(text);
+    }
+    let clipboard = &monitored.clipboard;
+    assert_eq!(
+        search_texts(clipboard, "clip"),
+        ["a clip", "clipping clips", "clipboard manager"]
+    );
+    assert_eq!(
+        search_texts(clipboard, "CLIP manager"),
+        ["clipboard manager"]
+    );
+    assert!(search_texts(clipboard, "missing").is_empty());
+}
+
+#[test]
+fn search_matches_html_rtf_and_files() {
+    let monitored = monitored(&history_config(10));
+    monitored.copy_with(|backend| {
+        backend.set_html("<p>caf&eacute; <b>bold</b>word<script>hidden()</script></p>".into())
+    });
+    monitored.copy_with(|backend| {
+        backend.set_rich_text(r"{\rtf1{\fonttbl{\f0 Arial;}}\f0 rich \b text\b0\par}".into())
+    });
+    monitored.copy_with(|backend| backend.set_files(vec!["/home/user/report.pdf".into()]));
+    let clipboard = &monitored.clipboard;
+
+    let search = |query: &str| clipboard.history_search(query, 0, 20).unwrap();
+    let html = search("boldword");
+    assert_eq!(html.total, 1);
+    assert_eq!(html.hits[0].highlights[0].field, SearchField::Html);
+    assert_eq!(search("hidden").total, 0);
+    let rtf = search("rich text");
+    assert_eq!(rtf.total, 1);
+    assert_eq!(rtf.hits[0].highlights[0].field, SearchField::Rtf);
+    assert_eq!(search("arial").total, 0);
+    let files = search("report");
+    assert_eq!(files.hits[0].highlights[0].field, SearchField::Files);
+    assert_eq!(files.hits[0].highlights[0].ranges, [[11, 17]]);
+}
+
+#[test]
+fn search_highlights_and_pages() {
+    let monitored = monitored(&history_config(10));
+    for text in ["one needle", "two needle", "three needle and needles"] {
+        monitored.copy_handled(text);
+    }
+    let clipboard = &monitored.clipboard;
+    let first = clipboard.history_search("needle", 0, 2).unwrap();
+    assert_eq!(first.total, 3);
+    assert_eq!(first.hits.len(), 2);
+    let second = clipboard.history_search("needle", 2, 2).unwrap();
+    assert_eq!(second.hits.len(), 1);
+
+    let hit = first
+        .hits
+        .iter()
+        .chain(&second.hits)
+        .find(|hit| hit.entry.content.text.as_deref() == Some("three needle and needles"))
+        .unwrap();
+    let highlight = &hit.highlights[0];
+    assert_eq!(highlight.snippet, "three needle and needles");
+    assert_eq!(highlight.ranges, [[6, 12], [17, 24]]);
+}
+
+#[test]
+fn search_finds_blobs_after_restart() {
+    let directory = TempDir::new("search");
+    let history = HistoryConfig {
+        blob_threshold: 8,
+        ..persisted_config(&directory)
+    };
+    {
+        let monitored = monitored(&history);
+        monitored.copy_handled("a long text with a keyword");
+    }
+    let clipboard = open(&history);
+    let results = clipboard.history_search("keyword", 0, 20).unwrap();
+    assert_eq!(results.total, 1);
+    assert_eq!(results.hits[0].entry.content.text, None);
+    assert_eq!(results.hits[0].highlights[0].ranges, [[19, 26]]);
+}
+
+fn filtered_texts(clipboard: &Clipboard, filter: HistoryFilter) -> Vec<String> {
+    clipboard
+        .history_list(&filter)
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.content.text.unwrap_or_default())
+        .collect()
+}
+
+#[test]
+fn pinned_entries_survive_eviction_and_clear() {
+    let monitored = monitored(&history_config(2));
+    monitored.copy_handled("a");
+    let clipboard = &monitored.clipboard;
+    let app = monitored.app.handle();
+    let pinned = clipboard.history_list(&HistoryFilter::default()).unwrap()[0].id;
+    clipboard.history_pin(app.clone(), pinned).unwrap();
+    for text in ["b", "c", "d"] {
+        monitored.copy_handled(text);
+    }
+    assert_eq!(texts(clipboard), ["d", "c", "a"]);
+
+    // copying a pinned clip again keeps it pinned
+    monitored.copy_handled("a");
+    assert_eq!(
+        filtered_texts(
+            clipboard,
+            HistoryFilter {
+                pinned: Some(true),
+                ..Default::default()
+            }
+        ),
+        ["a"]
+    );
+
+    clipboard.history_clear(app.clone()).unwrap();
+    assert_eq!(texts(clipboard), ["a"]);
+    let pinned = clipboard.history_list(&HistoryFilter::default()).unwrap()[0].id;
+    clipboard.history_unpin(app.clone(), pinned).unwrap();
+    clipboard.history_clear(app.clone()).unwrap();
+    assert!(texts(clipboard).is_empty());
+}
+
+#[test]
+fn list_filters_by_tag_and_format() {
+    let monitored = monitored(&history_config(10));
+    monitored.copy_handled("a");
+    monitored.copy_with(|backend| backend.set_html("<b>b</b>".into()));
+    monitored.copy_handled("c");
+    let clipboard = &monitored.clipboard;
+    let app = monitored.app.handle();
+    let entries = clipboard.history_list(&HistoryFilter::default()).unwrap();
+    clipboard
+        .history_set_tags(
+            app.clone(),
+            entries[2].id,
+            vec![" work ".into(), "work".into()],
+        )
+        .unwrap();
+    clipboard
+        .history_set_tags(app.clone(), entries[0].id, vec!["work".into(), "".into()])
+        .unwrap();
+    assert_eq!(clipboard.history_get(entries[2].id).unwrap().tags, ["work"]);
+
+    let by_tag = HistoryFilter {
+        tag: Some("work".into()),
+        ..Default::default()
+    };
+    assert_eq!(filtered_texts(clipboard, by_tag), ["c", "a"]);
+    let html = clipboard
+        .history_list(&HistoryFilter {
+            format: Some(HistoryFormat::Html),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(html.len(), 1);
+    assert_eq!(html[0].content.html.as_deref(), Some("<b>b</b>"));
+    assert!(clipboard
+        .history_set_tags(app.clone(), 1000, Vec::new())
+        .is_err());
+}
+
+#[test]
+fn pins_and_tags_are_persisted() {
+    let directory = TempDir::new("pins");
+    let history = HistoryConfig {
+        max_entries: 1,
+        ..persisted_config(&directory)
+    };
+    {
+        let monitored = monitored(&history);
+        monitored.copy_handled("a");
+        let app = monitored.app.handle();
+        let id = monitored
+            .clipboard
+            .history_list(&HistoryFilter::default())
+            .unwrap()[0]
+            .id;
+        monitored.clipboard.history_pin(app.clone(), id).unwrap();
+        monitored
+            .clipboard
+            .history_set_tags(app.clone(), id, vec!["kept".into()])
+            .unwrap();
+        monitored.copy_handled("b");
+        monitored.clipboard.history_clear(app.clone()).unwrap();
+    }
+    let clipboard = open(&history);
+    let entries = clipboard.history_list(&HistoryFilter::default()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].pinned);
+    assert_eq!(entries[0].tags, ["kept"]);
+}
+
+#[test]
+fn restore_writes_all_formats_without_new_entry() {
+    let monitored = monitored(&history_config(10));
+    monitored.copy_with(|backend| {
+        backend.set(vec![
+            ClipboardContent::Text("text".into()),
+            ClipboardContent::Html("<i>html</i>".into()),
+            ClipboardContent::Rtf(r"{\rtf1 rtf}".into()),
+            ClipboardContent::Files(vec!["/tmp/file".into()]),
+        ])
+    });
+    monitored.copy_handled("other");
+    let clipboard = &monitored.clipboard;
+    let before = clipboard.history_list(&HistoryFilter::default()).unwrap();
+
+    monitored.copy_with(|_| {
+        clipboard.history_restore(before[1].id).unwrap();
+        Ok(())
+    });
+    let backend = &monitored.backend;
+    assert_eq!(backend.get_text().unwrap(), "text");
+    assert_eq!(backend.get_html().unwrap(), "<i>html</i>");
+    assert_eq!(backend.get_rich_text().unwrap(), r"{\rtf1 rtf}");
+    assert_eq!(backend.get_files().unwrap(), ["/tmp/file"]);
+    let after: Vec<u64> = clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap()
+        .iter()
+        .map(|entry| entry.id)
+        .collect();
+    assert_eq!(
+        after,
+        before.iter().map(|entry| entry.id).collect::<Vec<_>>()
+    );
+}
+
+fn retention_config(retention: RetentionPolicy) -> HistoryConfig {
+    HistoryConfig {
+        retention,
+        ..history_config(10)
+    }
+}
+
+#[test]
+fn entries_expire_after_max_age() {
+    let monitored = monitored(&retention_config(RetentionPolicy {
+        max_age_secs: Some(60),
+        ..Default::default()
+    }));
+    monitored.copy_handled("old");
+    monitored.clock.advance(Duration::from_secs(30));
+    monitored.copy_handled("new");
+    assert_eq!(texts(&monitored.clipboard), ["new", "old"]);
+
+    monitored.clock.advance(Duration::from_secs(40));
+    monitored
+        .clipboard
+        .history_sweep(monitored.app.handle().clone())
+        .unwrap();
+    assert_eq!(texts(&monitored.clipboard), ["new"]);
+}
+
+#[test]
+fn total_size_evicts_oldest_entries() {
+    let monitored = monitored(&retention_config(RetentionPolicy {
+        max_total_bytes: Some(10),
+        ..Default::default()
+    }));
+    for text in ["aaaa", "bbbb", "cccc"] {
+        monitored.copy_handled(text);
+    }
+    assert_eq!(texts(&monitored.clipboard), ["cccc", "bbbb"]);
+    let entries = monitored
+        .clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap();
+    assert_eq!(entries[0].size, 4);
+}
+
+#[test]
+fn format_limits_only_apply_to_that_format() {
+    let monitored = monitored(&retention_config(RetentionPolicy {
+        formats: HashMap::from([(
+            HistoryFormat::Html,
+            FormatRetention {
+                max_entries: Some(1),
+                ..Default::default()
+            },
+        )]),
+        ..Default::default()
+    }));
+    let copy_html = |html: &str| {
+        monitored.copy_with(|backend| {
+            backend.set(vec![
+                ClipboardContent::Text(html.into()),
+                ClipboardContent::Html(html.into()),
+            ])
+        })
+    };
+    monitored.copy_handled("plain 1");
+    copy_html("html 1");
+    monitored.copy_handled("plain 2");
+    copy_html("html 2");
+    assert_eq!(
+        texts(&monitored.clipboard),
+        ["html 2", "plain 2", "plain 1"]
+    );
+}
+
+#[test]
+fn pinned_entries_do_not_expire() {
+    let monitored = monitored(&retention_config(RetentionPolicy {
+        max_age_secs: Some(60),
+        ..Default::default()
+    }));
+    monitored.copy_handled("pinned");
+    monitored.copy_handled("other");
+    let clipboard = &monitored.clipboard;
+    let app_handle = monitored.app.handle();
+    let pinned = clipboard.history_list(&HistoryFilter::default()).unwrap()[1].id;
+    clipboard.history_pin(app_handle.clone(), pinned).unwrap();
+
+    monitored.clock.advance(Duration::from_secs(120));
+    clipboard.history_sweep(app_handle.clone()).unwrap();
+    assert_eq!(texts(clipboard), ["pinned"]);
+}
+
+#[test]
+fn sweeper_emits_evicted_entries() {
+    let monitored = monitored(&retention_config(RetentionPolicy {
+        max_age_secs: Some(60),
+        sweep_interval_ms: 10,
+        ..Default::default()
+    }));
+    monitored.copy_handled("old");
+    let id = monitored
+        .clipboard
+        .history_list(&HistoryFilter::default())
+        .unwrap()[0]
+        .id;
+    let (sender, changes) = mpsc::channel();
+    monitored
+        .app
+        .listen_any("plugin:clipboard://history-changed", move |event| {
+            let _ = sender.send(event.payload().to_string());
+        });
+
+    monitored.clock.advance(Duration::from_secs(120));
+    let app_handle = monitored.app.handle().clone();
+    monitored
+        .clipboard
+        .start_history_sweeper(app_handle.clone());
+    monitored.clipboard.start_history_sweeper(app_handle);
+    let change: serde_json::Value =
+        serde_json::from_str(&changes.recv_timeout(TIMEOUT).unwrap()).unwrap();
+    assert_eq!(change["removed"], serde_json::json!([id]));
+    assert!(texts(&monitored.clipboard).is_empty());
+}
@@ -0,0 +1,285 @@
+#![cfg(feature = "mock")]
+
+use std::io::Cursor;
+
+use image::{ImageFormat, RgbaImage};
+use tauri_plugin_clipboard::{
+    Clipboard, Config, Error, ImageEncoding, ImageReadOptions, MockClipboard, RawImage,
+    ThumbnailFilter,
+};
+
+/// Noisy enough that the JPEG quality makes a difference.
+fn sample_image() -> RgbaImage {
+    RgbaImage::from_fn(64, 48, |x, y| {
+        let value = (x * 31 + y * 17) ^ (x * y);
+        image::Rgba([value as u8, (value >> 2) as u8, (x * 4) as u8, 200])
+    })
+}
+
+fn encode(image: &RgbaImage, format: ImageFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgba8(image.clone())
+        .write_to(&mut Cursor::new(&mut bytes), format)
+        .unwrap();
+    bytes
+}
+
+fn clipboard_with_image(config: Config) -> Clipboard {
+    let clipboard = Clipboard::with_config(Box::new(MockClipboard::new()), config);
+    clipboard
+        .write_image_binary(encode(&sample_image(), ImageFormat::Png))
+        .unwrap();
+    clipboard
+}
+
+fn read(clipboard: &Clipboard, encoding: ImageEncoding, quality: Option<u8>) -> Vec<u8> {
+    clipboard
+        .read_image_binary_with_options(ImageReadOptions {
+            encoding: Some(encoding),
+            quality,
+        })
+        .unwrap()
+}
+
+#[test]
+fn reads_every_encoding() {
+    let clipboard = clipboard_with_image(Config::default());
+    for (encoding, format) in [
+        (ImageEncoding::Png, ImageFormat::Png),
+        (ImageEncoding::Jpeg, ImageFormat::Jpeg),
+        (ImageEncoding::Webp, ImageFormat::WebP),
+        (ImageEncoding::Bmp, ImageFormat::Bmp),
+    ] {
+        let bytes = read(&clipboard, encoding, None);
+        assert_eq!(image::guess_format(&bytes).unwrap(), format);
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (64, 48));
+    }
+}
+
+#[test]
+fn default_encoding_comes_from_config() {
+    let clipboard = clipboard_with_image(Config {
+        image_encoding: ImageEncoding::Webp,
+        ..Default::default()
+    });
+    let bytes = clipboard.read_image_binary().unwrap();
+    assert_eq!(image::guess_format(&bytes).unwrap(), ImageFormat::WebP);
+}
+
+#[test]
+fn jpeg_quality_changes_size() {
+    let clipboard = clipboard_with_image(Config {
+        jpeg_quality: 95,
+        ..Default::default()
+    });
+    let high = read(&clipboard, ImageEncoding::Jpeg, None);
+    let low = read(&clipboard, ImageEncoding::Jpeg, Some(10));
+    assert!(low.len() < high.len());
+}
+
+#[test]
+fn webp_is_lossless() {
+    let clipboard = clipboard_with_image(Config::default());
+    let bytes = read(&clipboard, ImageEncoding::Webp, None);
+    assert_eq!(
+        image::load_from_memory(&bytes).unwrap().to_rgba8(),
+        sample_image()
+    );
+}
+
+#[test]
+fn writes_any_decodable_format() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    for format in [
+        ImageFormat::Jpeg,
+        ImageFormat::WebP,
+        ImageFormat::Bmp,
+        ImageFormat::Gif,
+        ImageFormat::Tiff,
+    ] {
+        let source = match format {
+            ImageFormat::Jpeg => image::DynamicImage::ImageRgba8(sample_image())
+                .to_rgb8()
+                .into(),
+            _ => image::DynamicImage::ImageRgba8(sample_image()),
+        };
+        let mut bytes = Vec::new();
+        source
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        clipboard.write_image_binary(bytes).unwrap();
+        let decoded = image::load_from_memory(&clipboard.read_image_binary().unwrap()).unwrap();
+        assert_eq!(
+            (decoded.width(), decoded.height()),
+            (64, 48),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn rgba_round_trip() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    let pixels = sample_image();
+    clipboard
+        .write_image_rgba(RawImage {
+            width: 64,
+            height: 48,
+            rgba: pixels.clone().into_raw(),
+        })
+        .unwrap();
+    let decoded = image::load_from_memory(&clipboard.read_image_binary().unwrap()).unwrap();
+    assert_eq!(decoded.to_rgba8(), pixels);
+
+    let raw = clipboard.read_image_rgba().unwrap();
+    assert_eq!((raw.width, raw.height), (64, 48));
+    assert_eq!(raw.rgba, pixels.into_raw());
+}
+
+#[test]
+fn rgba_write_checks_length() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    let err = clipboard
+        .write_image_rgba(RawImage {
+            width: 2,
+            height: 2,
+            rgba: vec![0; 15],
+        })
+        .unwrap_err();
+    assert!(matches!(err, Error::ImageDecode(_)));
+    assert!(!clipboard.has_image().unwrap());
+
+    let err = clipboard
+        .write_image_rgba(RawImage {
+            width: u32::MAX,
+            height: u32::MAX,
+            rgba: vec![0; 16],
+        })
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidRequest(_)));
+}
+
+#[test]
+fn thumbnail_keeps_aspect_ratio() {
+    let clipboard = clipboard_with_image(Config::default());
+    let thumbnail = clipboard
+        .read_image_thumbnail(
+            16,
+            16,
+            ImageReadOptions {
+                encoding: Some(ImageEncoding::Jpeg),
+                quality: None,
+            },
+            ThumbnailFilter::Lanczos3,
+        )
+        .unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (16, 12));
+    assert_eq!(
+        (thumbnail.original_width, thumbnail.original_height),
+        (64, 48)
+    );
+    assert_eq!(
+        image::guess_format(&thumbnail.bytes).unwrap(),
+        ImageFormat::Jpeg
+    );
+    let decoded = image::load_from_memory(&thumbnail.bytes).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (16, 12));
+}
+
+#[test]
+fn thumbnail_does_not_upscale() {
+    let clipboard = clipboard_with_image(Config::default());
+    let thumbnail = clipboard
+        .read_image_thumbnail(
+            100,
+            100,
+            ImageReadOptions::default(),
+            ThumbnailFilter::default(),
+        )
+        .unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (64, 48));
+    assert_eq!(
+        image::load_from_memory(&thumbnail.bytes)
+            .unwrap()
+            .to_rgba8(),
+        sample_image()
+    );
+}
+
+#[test]
+fn image_info_without_encoding() {
+    let clipboard = clipboard_with_image(Config::default());
+    let info = clipboard.image_info(ImageReadOptions::default()).unwrap();
+    assert_eq!((info.width, info.height), (64, 48));
+    assert_eq!(info.color_type, "rgba8");
+    assert!(info.has_alpha);
+    assert_eq!(info.bits_per_pixel, 32);
+    assert_eq!(info.encoding, ImageEncoding::Png);
+    assert!(info.estimated_size > 0);
+
+    let options = ImageReadOptions {
+        encoding: Some(ImageEncoding::Bmp),
+        quality: None,
+    };
+    let info = clipboard.image_info(options).unwrap();
+    let bmp = clipboard.read_image_binary_with_options(options).unwrap();
+    assert_eq!(info.estimated_size, bmp.len() as u64);
+}
+
+#[test]
+fn image_info_keeps_color_type() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    let mut bytes = Vec::new();
+    image::DynamicImage::new_rgb8(5, 3)
+        .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+        .unwrap();
+    clipboard.write_image_binary(bytes).unwrap();
+    let info = clipboard.image_info(ImageReadOptions::default()).unwrap();
+    assert_eq!((info.width, info.height), (5, 3));
+    assert_eq!(info.color_type, "rgb8");
+    assert!(!info.has_alpha);
+    assert_eq!(info.bits_per_pixel, 24);
+}
+
+#[test]
+fn image_info_reports_missing_image() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    assert!(matches!(
+        clipboard.image_info(ImageReadOptions::default()),
+        Err(Error::EmptyClipboard)
+    ));
+}
+
+#[test]
+fn binary_ipc_layouts() {
+    let image = RawImage {
+        width: 2,
+        height: 1,
+        rgba: vec![1, 2, 3, 4, 5, 6, 7, 8],
+    };
+    let bytes = image.to_bytes();
+    assert_eq!(&bytes[..8], [2, 0, 0, 0, 1, 0, 0, 0]);
+    assert_eq!(RawImage::from_bytes(bytes).unwrap(), image);
+    assert!(matches!(
+        RawImage::from_bytes(vec![2, 0, 0]),
+        Err(Error::ImageDecode(_))
+    ));
+
+    let clipboard = clipboard_with_image(Config::default());
+    let thumbnail = clipboard
+        .read_image_thumbnail(
+            32,
+            32,
+            ImageReadOptions::default(),
+            ThumbnailFilter::default(),
+        )
+        .unwrap();
+    let bytes = thumbnail.to_bytes();
+    assert_eq!(
+        &bytes[..16],
+        [32, 0, 0, 0, 24, 0, 0, 0, 64, 0, 0, 0, 48, 0, 0, 0]
+    );
+    assert_eq!(&bytes[16..], thumbnail.bytes);
+}
@@ -0,0 +1,121 @@
+#![cfg(feature = "mock")]
+
+use std::sync::mpsc::{self, Sender};
+
+use tauri_plugin_clipboard::clipboard_rs::{
+    common::RustImage, ClipboardContent, ClipboardHandler, ContentFormat, RustImageData,
+};
+use tauri_plugin_clipboard::{ClipboardBackend, MockClipboard};
+
+mod common;
+
+use common::TIMEOUT;
+
+const FORMAT: &str = "application/x-test";
+
+/// Sends on every clipboard change.
+struct Changes(Sender<()>);
+
+impl ClipboardHandler for Changes {
+    fn on_clipboard_change(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+fn image() -> RustImageData {
+    RustImageData::from_dynamic_image(image::DynamicImage::new_rgba8(3, 2))
+}
+
+#[test]
+fn round_trips_every_format() {
+    let backend = MockClipboard::new();
+    let only = |format: ContentFormat, mime: &str| {
+        for other in [
+            ContentFormat::Text,
+            ContentFormat::Html,
+            ContentFormat::Rtf,
+            ContentFormat::Image,
+            ContentFormat::Files,
+            ContentFormat::Other(FORMAT.to_string()),
+        ] {
+            let expected = std::mem::discriminant(&other) == std::mem::discriminant(&format);
+            assert_eq!(backend.has(other), expected);
+        }
+        assert_eq!(backend.available_formats().unwrap(), [mime]);
+    };
+
+    backend.set_text("text".into()).unwrap();
+    assert_eq!(backend.get_text().unwrap(), "text");
+    only(ContentFormat::Text, "text/plain");
+
+    backend.set_html("<b>html</b>".into()).unwrap();
+    assert_eq!(backend.get_html().unwrap(), "<b>html</b>");
+    only(ContentFormat::Html, "text/html");
+
+    backend.set_rich_text("{\\rtf1}".into()).unwrap();
+    assert_eq!(backend.get_rich_text().unwrap(), "{\\rtf1}");
+    only(ContentFormat::Rtf, "text/rtf");
+
+    backend.set_image(image()).unwrap();
+    assert_eq!(backend.get_image().unwrap().get_size(), (3, 2));
+    only(ContentFormat::Image, "image/png");
+
+    backend.set_files(vec!["file:///tmp/a.txt".into()]).unwrap();
+    assert_eq!(backend.get_files().unwrap(), ["file:///tmp/a.txt"]);
+    only(ContentFormat::Files, "text/uri-list");
+
+    backend.set_buffer(FORMAT, vec![1, 2, 3]).unwrap();
+    assert_eq!(backend.get_buffer(FORMAT).unwrap(), [1, 2, 3]);
+    only(ContentFormat::Other(FORMAT.to_string()), FORMAT);
+}
+
+#[test]
+fn writes_replace_the_whole_content() {
+    let backend = MockClipboard::new();
+    backend
+        .set(vec![
+            ClipboardContent::Text("text".into()),
+            ClipboardContent::Html("<b>html</b>".into()),
+            ClipboardContent::Other(FORMAT.into(), vec![1]),
+        ])
+        .unwrap();
+    assert_eq!(
+        backend.available_formats().unwrap(),
+        ["text/plain", "text/html", FORMAT]
+    );
+
+    backend.set_text("other".into()).unwrap();
+    assert_eq!(backend.get_text().unwrap(), "other");
+    assert!(backend.get_html().is_err());
+    assert!(backend.get_buffer(FORMAT).is_err());
+
+    backend.clear().unwrap();
+    assert!(backend.available_formats().unwrap().is_empty());
+    assert!(backend.get_text().is_err());
+}
+
+#[test]
+fn clones_share_content() {
+    let backend = MockClipboard::new();
+    let other = backend.clone();
+    other.set_text("shared".into()).unwrap();
+    assert_eq!(backend.get_text().unwrap(), "shared");
+}
+
+#[test]
+fn watchers_are_notified_until_stopped() {
+    let backend = MockClipboard::new();
+    let (sender, changes) = mpsc::channel();
+    let handle = backend.watch(Box::new(Changes(sender))).unwrap();
+
+    backend.set_text("a".into()).unwrap();
+    backend.clear().unwrap();
+    backend.settle();
+    assert_eq!(changes.try_iter().count(), 2);
+
+    handle.stop();
+    backend.set_text("b".into()).unwrap();
+    backend.settle();
+    // the watcher thread ends once it is unregistered
+    assert!(changes.recv_timeout(TIMEOUT).is_err());
+}
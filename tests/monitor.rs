@@ -0,0 +1,251 @@
+#![cfg(feature = "mock")]
+
+use std::time::Duration;
+
+use tauri_plugin_clipboard::{
+    clipboard_rs::ClipboardContent, Clipboard, ClipboardBackend, FormatEvent, MockClipboard,
+    MonitorOptions,
+};
+
+mod common;
+
+use common::Harness;
+
+#[test]
+fn debounce_emits_once_after_quiet_period() {
+    let harness = Harness::start(MonitorOptions {
+        debounce_ms: 100,
+        ..Default::default()
+    });
+    harness.copy("a");
+    harness.copy("b");
+    harness.copy("c");
+    harness.expect_no_update();
+
+    harness.clock.advance(Duration::from_millis(100));
+    harness.expect_update();
+    harness.expect_no_update();
+}
+
+#[test]
+fn throttle_drops_changes_within_window() {
+    let harness = Harness::start(MonitorOptions {
+        throttle_ms: 100,
+        ..Default::default()
+    });
+    harness.copy("a");
+    harness.expect_update();
+    harness.copy("b");
+    harness.expect_no_update();
+
+    harness.clock.advance(Duration::from_millis(100));
+    harness.expect_no_update();
+    harness.copy("c");
+    harness.expect_update();
+}
+
+#[test]
+fn throttle_delivers_trailing_change() {
+    let harness = Harness::start(MonitorOptions {
+        throttle_ms: 100,
+        trailing: true,
+        ..Default::default()
+    });
+    harness.copy("a");
+    harness.expect_update();
+    harness.copy("b");
+    harness.copy("c");
+    harness.expect_no_update();
+
+    harness.clock.advance(Duration::from_millis(100));
+    harness.expect_update();
+    harness.expect_no_update();
+}
+
+#[test]
+fn restarts_with_new_options() {
+    let harness = Harness::start(MonitorOptions {
+        debounce_ms: 100,
+        ..Default::default()
+    });
+    harness.copy("a");
+    harness.expect_no_update();
+
+    harness
+        .clipboard
+        .start_monitor_with_options(harness.app.handle().clone(), MonitorOptions::default())
+        .unwrap();
+    assert!(harness.clipboard.is_monitor_running().unwrap());
+    harness.copy("b");
+    harness.expect_update();
+}
+
+#[test]
+fn identical_content_emits_once() {
+    let harness = Harness::start(MonitorOptions::default());
+    harness.copy("a");
+    let first = harness.next_update();
+    assert_eq!(first["sequence"], 1);
+    harness.copy("a");
+    harness.copy("b");
+    // the second copy of "a" was skipped, the next update is "b"
+    let second = harness.next_update();
+    assert_eq!(second["sequence"], 2);
+    assert_ne!(second["fingerprint"], first["fingerprint"]);
+    assert_eq!(
+        second["fingerprint"],
+        harness.clipboard.fingerprint().unwrap().as_str()
+    );
+}
+
+#[test]
+fn fingerprint_depends_on_content_only() {
+    let backend = MockClipboard::new();
+    let clipboard = Clipboard::new(Box::new(backend.clone()));
+    backend.set_text("a".to_string()).unwrap();
+    let a = clipboard.fingerprint().unwrap();
+    assert_eq!(clipboard.fingerprint().unwrap(), a);
+    assert_eq!(a.len(), 16);
+
+    backend.set_text("b".to_string()).unwrap();
+    let b = clipboard.fingerprint().unwrap();
+    assert_ne!(b, a);
+    backend.set_html("a".to_string()).unwrap();
+    assert_ne!(clipboard.fingerprint().unwrap(), a);
+
+    backend.set_text("a".to_string()).unwrap();
+    assert_eq!(clipboard.fingerprint().unwrap(), a);
+}
+
+#[test]
+fn fingerprint_is_stable() {
+    // persisted in the history, so it must not change across platforms or releases
+    let backend = MockClipboard::new();
+    let clipboard = Clipboard::new(Box::new(backend.clone()));
+    backend.set_text("hello".to_string()).unwrap();
+    assert_eq!(clipboard.fingerprint().unwrap(), "7e1bbab3ab0c4798");
+}
+
+#[test]
+fn files_take_precedence_over_text() {
+    let harness = Harness::start(MonitorOptions::default());
+    let files = harness.listen("files-changed");
+    let text = harness.listen("text-changed");
+    harness
+        .clipboard
+        .subscribe_format_events(&[FormatEvent::Files, FormatEvent::Text])
+        .unwrap();
+
+    harness
+        .backend
+        .set(vec![
+            ClipboardContent::Files(vec!["file:///tmp/a.txt".to_string()]),
+            ClipboardContent::Text("/tmp/a.txt".to_string()),
+        ])
+        .unwrap();
+    // format events are emitted before the update
+    harness.expect_update();
+    assert_eq!(
+        files.try_recv().unwrap()["value"],
+        serde_json::json!(["file:///tmp/a.txt"])
+    );
+    assert!(text.try_recv().is_err());
+
+    harness.copy("a");
+    harness.expect_update();
+    assert_eq!(text.try_recv().unwrap()["value"], "a");
+    assert!(files.try_recv().is_err());
+}
+
+#[test]
+fn format_events_are_counted_per_subscriber() {
+    let harness = Harness::start(MonitorOptions::default());
+    let text = harness.listen("text-changed");
+    harness.copy("unsubscribed");
+    harness.expect_update();
+    assert!(text.try_recv().is_err());
+
+    let clipboard = &harness.clipboard;
+    clipboard
+        .subscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    clipboard
+        .subscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    clipboard
+        .unsubscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    harness.copy("one subscriber left");
+    harness.expect_update();
+    assert_eq!(text.try_recv().unwrap()["value"], "one subscriber left");
+
+    clipboard
+        .unsubscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    // unsubscribing more often than subscribing does not underflow
+    clipboard
+        .unsubscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    harness.copy("no subscriber");
+    harness.expect_update();
+    assert!(text.try_recv().is_err());
+
+    clipboard
+        .subscribe_format_events(&[FormatEvent::Text])
+        .unwrap();
+    harness.copy("subscribed again");
+    harness.expect_update();
+    assert_eq!(text.try_recv().unwrap()["value"], "subscribed again");
+}
+
+#[test]
+fn change_event_payload() {
+    let harness = Harness::start(MonitorOptions::default());
+    harness.copy("a");
+    let event = harness.next_update();
+    assert_eq!(event["sequence"], 1);
+    assert_eq!(
+        event["available"],
+        serde_json::json!({
+            "text": true,
+            "html": false,
+            "rtf": false,
+            "image": false,
+            "files": false,
+        })
+    );
+    assert_eq!(event["content"], serde_json::Value::Null);
+
+    harness.backend.set_html("<b>b</b>".to_string()).unwrap();
+    let event = harness.next_update();
+    assert_eq!(event["sequence"], 2);
+    assert_eq!(event["available"]["html"], true);
+    assert_eq!(event["available"]["text"], false);
+}
+
+#[test]
+fn change_event_includes_content() {
+    let harness = Harness::start(MonitorOptions {
+        include_content: true,
+        ..Default::default()
+    });
+    harness
+        .backend
+        .set(vec![
+            ClipboardContent::Text("a".to_string()),
+            ClipboardContent::Html("<b>a</b>".to_string()),
+        ])
+        .unwrap();
+    let event = harness.next_update();
+    assert_eq!(event["sequence"], 1);
+    assert_eq!(
+        event["content"],
+        serde_json::json!({
+            "text": "a",
+            "html": "<b>a</b>",
+            "rtf": null,
+            "image": null,
+            "files": null,
+        })
+    );
+}
@@ -0,0 +1,77 @@
+#![cfg(feature = "mock")]
+
+use tauri_plugin_clipboard::{
+    clipboard_rs::{common::RustImage, ClipboardContent, RustImageData},
+    Clipboard, ClipboardBackend, Config, MockClipboard, SnapshotOptions,
+};
+
+fn open(config: Config) -> (Clipboard, MockClipboard) {
+    let backend = MockClipboard::new();
+    backend
+        .set(vec![
+            ClipboardContent::Text("text".into()),
+            ClipboardContent::Html("<b>longer html</b>".into()),
+            ClipboardContent::Image(RustImageData::from_dynamic_image(
+                image::RgbaImage::new(4, 4).into(),
+            )),
+        ])
+        .unwrap();
+    (
+        Clipboard::with_config(Box::new(backend.clone()), config),
+        backend,
+    )
+}
+
+#[test]
+fn reads_every_format() {
+    let (clipboard, _backend) = open(Config::default());
+    let contents = clipboard.read_all(&SnapshotOptions::default()).unwrap();
+    assert!(contents.available.text && contents.available.html && contents.available.image);
+    assert!(!contents.available.rtf);
+    let content = contents.content;
+    assert_eq!(content.text.as_deref(), Some("text"));
+    assert_eq!(content.html.as_deref(), Some("<b>longer html</b>"));
+    assert_eq!(content.rtf, None);
+    assert_eq!(content.files, None);
+    assert!(content.image.is_some());
+}
+
+#[test]
+fn skips_image_and_large_payloads() {
+    let (clipboard, _backend) = open(Config::default());
+    let contents = clipboard
+        .read_all(&SnapshotOptions {
+            skip_image: true,
+            max_text_size: Some(10),
+            ..Default::default()
+        })
+        .unwrap();
+    assert!(contents.available.image);
+    assert_eq!(contents.content.image, None);
+    assert_eq!(contents.content.html, None);
+    assert_eq!(contents.content.text.as_deref(), Some("text"));
+}
+
+#[test]
+fn config_limits_still_apply() {
+    let (clipboard, _backend) = open(Config {
+        max_text_size: Some(2),
+        ..Default::default()
+    });
+    let contents = clipboard
+        .read_all(&SnapshotOptions {
+            max_text_size: Some(100),
+            max_image_size: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(contents.content.text, None);
+    assert_eq!(contents.content.image, None);
+}
+
+#[test]
+fn empty_clipboard_has_no_content() {
+    let clipboard = Clipboard::new(Box::new(MockClipboard::new()));
+    let contents = clipboard.read_all(&SnapshotOptions::default()).unwrap();
+    assert!(!contents.available.any());
+}
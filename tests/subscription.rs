@@ -0,0 +1,63 @@
+#![cfg(feature = "mock")]
+
+use std::{future::poll_fn, pin::Pin, sync::mpsc, time::Duration};
+
+use futures_core::Stream;
+use tauri_plugin_clipboard::{Clipboard, ClipboardBackend, MockClipboard};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn start() -> (
+    tauri::App<tauri::test::MockRuntime>,
+    Clipboard,
+    MockClipboard,
+) {
+    let app = tauri::test::mock_app();
+    let backend = MockClipboard::new();
+    let clipboard = Clipboard::new(Box::new(backend.clone()));
+    clipboard.start_monitor(app.handle().clone()).unwrap();
+    (app, clipboard, backend)
+}
+
+#[test]
+fn subscribers_receive_changes_until_dropped() {
+    let (_app, clipboard, backend) = start();
+    let (first_sender, first) = mpsc::channel();
+    let (second_sender, second) = mpsc::channel();
+    let first_subscription = clipboard
+        .subscribe(move |event| {
+            let _ = first_sender.send(event.sequence);
+        })
+        .unwrap();
+    let _second_subscription = clipboard
+        .subscribe(move |event| {
+            let _ = second_sender.send(event.sequence);
+        })
+        .unwrap();
+
+    backend.set_text("a".to_string()).unwrap();
+    assert_eq!(first.recv_timeout(TIMEOUT).unwrap(), 1);
+    assert_eq!(second.recv_timeout(TIMEOUT).unwrap(), 1);
+
+    drop(first_subscription);
+    backend.set_text("b".to_string()).unwrap();
+    assert_eq!(second.recv_timeout(TIMEOUT).unwrap(), 2);
+    backend.settle();
+    assert!(first.try_recv().is_err());
+}
+
+#[test]
+fn watch_streams_changes() {
+    let (_app, clipboard, backend) = start();
+    let mut watch = clipboard.watch().unwrap();
+    let mut next = || {
+        tauri::async_runtime::block_on(poll_fn(|cx| Pin::new(&mut watch).poll_next(cx))).unwrap()
+    };
+
+    backend.set_text("a".to_string()).unwrap();
+    assert_eq!(next().sequence, 1);
+    backend.set_html("<b>b</b>".to_string()).unwrap();
+    let event = next();
+    assert_eq!(event.sequence, 2);
+    assert!(event.available.html);
+}
@@ -0,0 +1,230 @@
+#![cfg(feature = "mock")]
+
+use std::sync::{
+    mpsc::{self, Receiver},
+    Mutex,
+};
+use std::time::Duration;
+
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri_plugin_clipboard::{
+    ChunkedReadOptions, Clipboard, Config, Error, MockClipboard, MockClock, TransferEvent,
+    TransferFormat, TransferProgress, WRITE_TIMEOUT,
+};
+
+#[derive(Debug, PartialEq)]
+enum Message {
+    Event(TransferEvent),
+    Chunk(Vec<u8>),
+}
+
+fn message(body: InvokeResponseBody) -> Message {
+    match body {
+        InvokeResponseBody::Json(json) => Message::Event(serde_json::from_str(&json).unwrap()),
+        InvokeResponseBody::Raw(bytes) => Message::Chunk(bytes),
+    }
+}
+
+/// A channel forwarding its messages, the read thread blocks after `Started` until `resume`
+/// receives a value.
+fn channel(resume: Option<Receiver<()>>) -> (Channel, Receiver<Message>) {
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    let resume = Mutex::new(resume);
+    let channel = Channel::new(move |body| {
+        let message = message(body);
+        let started = matches!(message, Message::Event(TransferEvent::Started { .. }));
+        tx.lock().unwrap().send(message).unwrap();
+        if started {
+            if let Some(resume) = resume.lock().unwrap().as_ref() {
+                resume.recv().unwrap();
+            }
+        }
+        Ok(())
+    });
+    (channel, rx)
+}
+
+fn next(rx: &Receiver<Message>) -> Message {
+    rx.recv_timeout(Duration::from_secs(5)).unwrap()
+}
+
+fn open() -> Clipboard {
+    Clipboard::new(Box::new(MockClipboard::new()))
+}
+
+#[test]
+fn reads_text_in_chunks() {
+    let clipboard = open();
+    let text = "0123456789".repeat(10);
+    clipboard.write_text(text.clone()).unwrap();
+    let (channel, rx) = channel(None);
+    let id = clipboard
+        .read_chunked(
+            TransferFormat::Text,
+            ChunkedReadOptions {
+                chunk_size: Some(30),
+                ..Default::default()
+            },
+            channel,
+        )
+        .unwrap();
+
+    assert_eq!(
+        next(&rx),
+        Message::Event(TransferEvent::Started { id, size: 100 })
+    );
+    let mut bytes = Vec::new();
+    let mut progress = Vec::new();
+    loop {
+        match next(&rx) {
+            Message::Chunk(chunk) => {
+                assert!(chunk.len() <= 30);
+                bytes.extend(chunk);
+            }
+            Message::Event(TransferEvent::Progress(event)) => progress.push(event.transferred),
+            Message::Event(TransferEvent::Finished { id: finished }) => {
+                assert_eq!(finished, id);
+                break;
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+    assert_eq!(bytes, text.as_bytes());
+    assert_eq!(progress, [30, 60, 90, 100]);
+    // the transfer is gone once finished
+    assert!(matches!(
+        clipboard.cancel_transfer(id),
+        Err(Error::TransferNotFound(_))
+    ));
+}
+
+#[test]
+fn cancels_read() {
+    let clipboard = open();
+    clipboard.write_text("a".repeat(64)).unwrap();
+    let (resume_tx, resume_rx) = mpsc::channel();
+    let (channel, rx) = channel(Some(resume_rx));
+    let id = clipboard
+        .read_chunked(
+            TransferFormat::Text,
+            ChunkedReadOptions {
+                chunk_size: Some(8),
+                ..Default::default()
+            },
+            channel,
+        )
+        .unwrap();
+
+    assert_eq!(
+        next(&rx),
+        Message::Event(TransferEvent::Started { id, size: 64 })
+    );
+    clipboard.cancel_transfer(id).unwrap();
+    resume_tx.send(()).unwrap();
+    assert_eq!(next(&rx), Message::Event(TransferEvent::Cancelled { id }));
+}
+
+#[test]
+fn writes_in_chunks() {
+    let clipboard = open();
+    let text = "chunked é text";
+    let bytes = text.as_bytes();
+    let id = clipboard
+        .write_chunked_begin(TransferFormat::Text, bytes.len() as u64)
+        .unwrap();
+    let mut last = None;
+    for chunk in bytes.chunks(4) {
+        last = Some(clipboard.write_chunked_append(id, chunk).unwrap());
+    }
+    assert_eq!(
+        last,
+        Some(TransferProgress {
+            id,
+            transferred: bytes.len() as u64,
+            size: bytes.len() as u64,
+        })
+    );
+    clipboard.write_chunked_finish(id).unwrap();
+    assert_eq!(clipboard.read_text().unwrap(), text);
+
+    let mut png = Vec::new();
+    image::DynamicImage::new_rgba8(3, 2)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let id = clipboard
+        .write_chunked_begin(TransferFormat::Image, png.len() as u64)
+        .unwrap();
+    for chunk in png.chunks(16) {
+        clipboard.write_chunked_append(id, chunk).unwrap();
+    }
+    clipboard.write_chunked_finish(id).unwrap();
+    assert_eq!(clipboard.read_image_rgba().unwrap().width, 3);
+}
+
+#[test]
+fn rejects_invalid_writes() {
+    let clipboard = Clipboard::with_config(
+        Box::new(MockClipboard::new()),
+        Config {
+            max_text_size: Some(8),
+            ..Default::default()
+        },
+    );
+    assert!(matches!(
+        clipboard.write_chunked_begin(TransferFormat::Text, 9),
+        Err(Error::PayloadTooLarge { size: 9, limit: 8 })
+    ));
+
+    let id = clipboard
+        .write_chunked_begin(TransferFormat::Text, 4)
+        .unwrap();
+    clipboard.write_chunked_append(id, b"abc").unwrap();
+    assert!(matches!(
+        clipboard.write_chunked_append(id, b"de"),
+        Err(Error::InvalidRequest(_))
+    ));
+    assert!(matches!(
+        clipboard.write_chunked_finish(id),
+        Err(Error::InvalidRequest(_))
+    ));
+    assert!(matches!(
+        clipboard.write_chunked_finish(id),
+        Err(Error::TransferNotFound(_))
+    ));
+
+    let id = clipboard
+        .write_chunked_begin(TransferFormat::Text, 2)
+        .unwrap();
+    clipboard.cancel_transfer(id).unwrap();
+    assert!(matches!(
+        clipboard.write_chunked_append(id, b"ab"),
+        Err(Error::TransferNotFound(_))
+    ));
+    assert!(!clipboard.has_text().unwrap());
+}
+
+#[test]
+fn drops_stale_writes() {
+    let clock = MockClock::new();
+    let clipboard = open().with_clock(clock.clone());
+    let stale = clipboard
+        .write_chunked_begin(TransferFormat::Text, 4)
+        .unwrap();
+    clipboard.write_chunked_append(stale, b"ab").unwrap();
+    let active = clipboard
+        .write_chunked_begin(TransferFormat::Text, 4)
+        .unwrap();
+
+    clock.advance(WRITE_TIMEOUT / 2);
+    clipboard.write_chunked_append(active, b"ab").unwrap();
+    clock.advance(WRITE_TIMEOUT / 2);
+    // every append counts as activity
+    clipboard.write_chunked_append(active, b"cd").unwrap();
+    assert!(matches!(
+        clipboard.write_chunked_append(stale, b"cd"),
+        Err(Error::TransferNotFound(_))
+    ));
+    clipboard.write_chunked_finish(active).unwrap();
+    assert_eq!(clipboard.read_text().unwrap(), "abcd");
+}
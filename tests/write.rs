@@ -0,0 +1,92 @@
+#![cfg(feature = "mock")]
+
+use std::io::Cursor;
+
+use tauri_plugin_clipboard::{
+    Clipboard, ClipboardBackend, ClipboardItem, Config, Error, MockClipboard,
+};
+
+fn png_bytes() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    image::RgbaImage::new(2, 3)
+        .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .unwrap();
+    bytes
+}
+
+fn open(config: Config) -> (Clipboard, MockClipboard) {
+    let backend = MockClipboard::new();
+    (
+        Clipboard::with_config(Box::new(backend.clone()), config),
+        backend,
+    )
+}
+
+#[test]
+fn write_sets_every_format() {
+    let (clipboard, backend) = open(Config::default());
+    clipboard
+        .write(vec![
+            ClipboardItem::Text {
+                text: "text".into(),
+            },
+            ClipboardItem::Html {
+                html: "<b>html</b>".into(),
+            },
+            ClipboardItem::Rtf {
+                rtf: r"{\rtf1 rtf}".into(),
+            },
+            ClipboardItem::Image { bytes: png_bytes() },
+            ClipboardItem::Files {
+                paths: vec!["/tmp/file".into()],
+            },
+        ])
+        .unwrap();
+    assert_eq!(backend.get_text().unwrap(), "text");
+    assert_eq!(backend.get_html().unwrap(), "<b>html</b>");
+    assert_eq!(backend.get_rich_text().unwrap(), r"{\rtf1 rtf}");
+    assert_eq!(clipboard.read_files().unwrap(), ["/tmp/file"]);
+    let image = image::load_from_memory(&clipboard.read_image_binary().unwrap()).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 3));
+}
+
+#[test]
+fn write_replaces_previous_content() {
+    let (clipboard, backend) = open(Config::default());
+    backend.set_rich_text(r"{\rtf1 old}".into()).unwrap();
+    clipboard
+        .write(vec![ClipboardItem::Text { text: "new".into() }])
+        .unwrap();
+    assert_eq!(backend.get_text().unwrap(), "new");
+    assert!(!clipboard.has_rtf().unwrap());
+}
+
+#[test]
+fn invalid_item_writes_nothing() {
+    let (clipboard, backend) = open(Config {
+        max_text_size: Some(4),
+        ..Default::default()
+    });
+    backend.set_text("old".into()).unwrap();
+
+    let err = clipboard
+        .write(vec![
+            ClipboardItem::Text { text: "ok".into() },
+            ClipboardItem::Html {
+                html: "<p>too long</p>".into(),
+            },
+        ])
+        .unwrap_err();
+    assert!(matches!(err, Error::PayloadTooLarge { limit: 4, .. }));
+
+    let err = clipboard
+        .write(vec![
+            ClipboardItem::Text { text: "ok".into() },
+            ClipboardItem::Image {
+                bytes: vec![1, 2, 3],
+            },
+        ])
+        .unwrap_err();
+    assert!(matches!(err, Error::ImageDecode(_)));
+    assert_eq!(backend.get_text().unwrap(), "old");
+}